@@ -0,0 +1,176 @@
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use std::ops::RangeInclusive;
+
+/// Axis aligned bounding box stored as its minimum and maximum corners
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb<T: VElem> {
+    min: Point3<T>,
+    max: Point3<T>,
+}
+
+impl<T: VElem> Aabb<T> {
+    /// creates the box spanned by two extreme points, in any order
+    pub fn new(a: Point3<T>, b: Point3<T>) -> Self {
+        Self {
+            min: Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z())),
+            max: Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z())),
+        }
+    }
+
+    /// box containing nothing, the identity for [`Aabb::union`]
+    pub fn empty() -> Self {
+        Self {
+            min: Point3::from([T::infinity(); 3]),
+            max: Point3::from([T::neg_infinity(); 3]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|axis| self.min[axis] > self.max[axis])
+    }
+
+    /// smallest box enclosing both boxes
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Point3::new(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Point3::new(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+
+    /// grows every side thinner than `delta` to `delta` around its center,
+    /// so that flat objects still get a box with some volume
+    pub fn pad(&self, delta: T) -> Self {
+        let mut padded = *self;
+        let half: T = delta * 0.5.into();
+        for axis in 0..3 {
+            if self.max[axis] - self.min[axis] < delta {
+                let mid = (self.min[axis] + self.max[axis]) * 0.5.into();
+                padded.min[axis] = mid - half;
+                padded.max[axis] = mid + half;
+            }
+        }
+        padded
+    }
+
+    /// the eight corner points of the box
+    pub fn corners(&self) -> [Point3<T>; 8] {
+        std::array::from_fn(|i| {
+            let pick = |axis: usize| {
+                if i & (1 << axis) == 0 {
+                    self.min[axis]
+                } else {
+                    self.max[axis]
+                }
+            };
+            Point3::new(pick(0), pick(1), pick(2))
+        })
+    }
+
+    pub fn centroid(&self) -> Point3<T> {
+        (self.min + self.max) * Into::<T>::into(0.5)
+    }
+
+    pub fn extent(&self) -> Vec3<T> {
+        if self.is_empty() {
+            Vec3::default()
+        } else {
+            self.max - self.min
+        }
+    }
+
+    pub fn surface_area(&self) -> T {
+        let e = self.extent();
+        Into::<T>::into(2.0) * (e.x() * e.y() + e.y() * e.z() + e.z() * e.x())
+    }
+
+    /// slab test, the box is hit if the ray overlaps all three slabs within `ray_t`
+    pub fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> bool {
+        let mut t_min = *ray_t.start();
+        let mut t_max = *ray_t.end();
+        for axis in 0..3 {
+            let inv_d = T::one() / ray.direction()[axis];
+            let mut t0 = (self.min[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < T::zero() {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn new_orders_corners() {
+        let b = Aabb::new(Point3::new(1.0, -1.0, 2.0), Point3::new(-1.0, 1.0, 0.0));
+        assert_eq!(b.min, Point3::new(-1.0, -1.0, 0.0));
+        assert_eq!(b.max, Point3::new(1.0, 1.0, 2.0));
+        assert_eq!(b.centroid(), Point3::new(0.0, 0.0, 1.0));
+        assert_eq!(b.surface_area(), 24.0);
+    }
+
+    #[test]
+    fn union_with_empty() {
+        let b = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let e = Aabb::<f32>::empty();
+        assert!(e.is_empty());
+        assert_eq!(e.surface_area(), 0.0);
+        assert_eq!(e.union(&b), b);
+        let u = b.union(&Aabb::new(
+            Point3::new(2.0, 2.0, 2.0),
+            Point3::new(3.0, 3.0, 3.0),
+        ));
+        assert_eq!(u.min, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(u.max, Point3::new(3.0, 3.0, 3.0));
+    }
+
+    #[test]
+    fn pad_test() {
+        let b = Aabb::new(Point3::new(0.0, 0.0, 1.0), Point3::new(2.0, 2.0, 1.0)).pad(0.5);
+        assert_eq!(b.min, Point3::new(0.0, 0.0, 0.75));
+        assert_eq!(b.max, Point3::new(2.0, 2.0, 1.25));
+    }
+
+    #[test]
+    fn corners_test() {
+        let b = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 3.0));
+        let corners = b.corners();
+        assert_eq!(corners[0], Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(corners[7], Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(corners[5], Point3::new(1.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn hit_and_miss() {
+        let b = Aabb::new(Point3::new(-1.0, -1.0, -6.0), Point3::new(1.0, 1.0, -4.0));
+        let ray = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]);
+        assert!(b.hit(&ray, 0.0..=10.0));
+        // box is beyond the range
+        assert!(!b.hit(&ray, 0.0..=3.0));
+        // pointing away
+        let ray = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert!(!b.hit(&ray, 0.0..=10.0));
+        // parallel to the slab outside of it
+        let ray = Ray::new([2.0, 0.0, 0.0], [0.0, 0.0, -1.0]);
+        assert!(!b.hit(&ray, 0.0..=10.0));
+    }
+}
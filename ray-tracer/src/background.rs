@@ -0,0 +1,99 @@
+use crate::color::Color;
+use crate::environment::EnvironmentMap;
+use crate::pdf::Pdf;
+use crate::ray::Ray;
+use crate::velem::VElem;
+use std::sync::Arc;
+
+/// Radiance seen by rays escaping the scene
+#[derive(Clone, Debug, PartialEq)]
+pub enum Background<T: VElem> {
+    /// same color in every direction
+    Solid(Color<T>),
+    /// vertical blend from `bottom` when looking straight down to `top` when looking straight up
+    Gradient { bottom: Color<T>, top: Color<T> },
+    /// radiance looked up from an equirectangular image
+    Environment(Arc<EnvironmentMap<T>>),
+}
+
+impl<T: VElem> Default for Background<T> {
+    fn default() -> Self {
+        Self::sky()
+    }
+}
+
+impl<T: VElem> Background<T> {
+    /// no light from outside of the scene, for closed rooms or scenes lit only by emitters
+    pub fn black() -> Self {
+        Self::Solid(Color::default())
+    }
+
+    /// white to light blue sky gradient
+    pub fn sky() -> Self {
+        Self::Gradient {
+            bottom: Color::from([T::one(); 3]),
+            top: Color::from([0.5.into(), 0.7.into(), T::one()]),
+        }
+    }
+
+    pub fn color(&self, ray: &Ray<T>) -> Color<T> {
+        match self {
+            Self::Solid(color) => *color,
+            Self::Gradient { bottom, top } => {
+                let unit_direction = ray.direction().unit_vector();
+                let a: T = (unit_direction.y() + T::one()) * 0.5.into();
+                *bottom * (T::one() - a) + *top * a
+            }
+            Self::Environment(map) => map.radiance(ray.direction()),
+        }
+    }
+
+    /// density for importance sampling the background as a light, only environment maps have
+    /// bright spots worth aiming at
+    pub fn pdf(&self) -> Option<&dyn Pdf<T>> {
+        match self {
+            Self::Environment(map) => Some(map.as_ref()),
+            Self::Solid(_) | Self::Gradient { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn solid_test() {
+        let bg = Background::Solid(Color::new(0.1, 0.2, 0.3));
+        let r = Ray::new([0.0, 0.0, 0.0], [1.0, 2.0, 3.0]);
+        assert_eq!(bg.color(&r), Color::new(0.1, 0.2, 0.3));
+        assert_eq!(Background::black().color(&r), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn gradient_test() {
+        let bg = Background::Gradient {
+            bottom: Color::new(1.0, 0.0, 0.0),
+            top: Color::new(0.0, 0.0, 1.0),
+        };
+        let up = Ray::new([0.0, 0.0, 0.0], [0.0, 2.0, 0.0]);
+        let down = Ray::new([0.0, 0.0, 0.0], [0.0, -2.0, 0.0]);
+        let side = Ray::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        assert_eq!(bg.color(&up), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(bg.color(&down), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(bg.color(&side), Color::new(0.5, 0.0, 0.5));
+    }
+
+    #[test]
+    fn environment_test() {
+        let mut image = crate::image::Image::new(2, 2);
+        image[(0, 0)] = Color::new(1.0, 1.0, 1.0);
+        image[(1, 0)] = Color::new(1.0, 1.0, 1.0);
+        let bg = Background::Environment(Arc::new(EnvironmentMap::new(image).unwrap()));
+        let up = Ray::new([0.0, 0.0, 0.0], [0.0, 2.0, 0.1]);
+        let down = Ray::new([0.0, 0.0, 0.0], [0.0, -2.0, 0.1]);
+        assert_eq!(bg.color(&up), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(bg.color(&down), Color::new(0.0, 0.0, 0.0));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::ray::Ray;
+use crate::velem::VElem;
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+type Entry<T> = (Arc<dyn Hittable<T>>, Aabb<T>);
+
+/// Bounding volume hierarchy over a list of hittables.
+/// Every node holds two children and the box enclosing both, so a ray only descends into
+/// the subtrees whose boxes it actually crosses.
+pub struct BvhNode<T: VElem> {
+    left: Arc<dyn Hittable<T>>,
+    right: Arc<dyn Hittable<T>>,
+    bbox: Aabb<T>,
+}
+
+impl<T: VElem> BvhNode<T> {
+    pub fn new(objects: HittableList<T>) -> Self {
+        let mut objects: Vec<Entry<T>> = objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                (object, bbox)
+            })
+            .collect();
+        Self::build(&mut objects)
+    }
+
+    fn build(objects: &mut [Entry<T>]) -> Self {
+        let bbox = objects
+            .iter()
+            .fold(Aabb::empty(), |acc, (_, bbox)| acc.union(bbox));
+        let (left, right): (Arc<dyn Hittable<T>>, Arc<dyn Hittable<T>>) = match objects {
+            [] => {
+                let empty: Arc<dyn Hittable<T>> = Arc::new(HittableList::new());
+                (empty.clone(), empty)
+            }
+            [(only, _)] => (only.clone(), only.clone()),
+            [(a, _), (b, _)] => (a.clone(), b.clone()),
+            _ => {
+                let (axis, split) = Self::sah_split(objects);
+                sort_by_centroid(objects, axis);
+                let (l, r) = objects.split_at_mut(split);
+                (Arc::new(Self::build(l)), Arc::new(Self::build(r)))
+            }
+        };
+        Self { left, right, bbox }
+    }
+
+    /// Picks the axis and position of the split that minimises the surface area heuristic
+    /// `SA(left) * N(left) + SA(right) * N(right)` over the objects sorted by box centroid.
+    fn sah_split(objects: &mut [Entry<T>]) -> (usize, usize) {
+        let n = objects.len();
+        let mut best = (0, n / 2);
+        let mut best_cost = T::infinity();
+        let mut right_areas = vec![T::zero(); n];
+        for axis in 0..3 {
+            sort_by_centroid(objects, axis);
+            // right_areas[i] -> area of the box around objects[i..]
+            let mut acc = Aabb::empty();
+            for i in (1..n).rev() {
+                acc = acc.union(&objects[i].1);
+                right_areas[i] = acc.surface_area();
+            }
+            let mut acc = Aabb::empty();
+            for i in 1..n {
+                acc = acc.union(&objects[i - 1].1);
+                let cost = acc.surface_area() * (i as f32).into()
+                    + right_areas[i] * ((n - i) as f32).into();
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = (axis, i);
+                }
+            }
+        }
+        best
+    }
+}
+
+fn sort_by_centroid<T: VElem>(objects: &mut [Entry<T>], axis: usize) {
+    objects.sort_by(|a, b| {
+        a.1.centroid()[axis]
+            .partial_cmp(&b.1.centroid()[axis])
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+impl<T: VElem> Hittable<T> for BvhNode<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        if !self.bbox.hit(ray, ray_t.clone()) {
+            return None;
+        }
+        let hit_left = self.left.hit(ray, ray_t.clone());
+        let closest = hit_left.as_ref().map_or(*ray_t.end(), |hit| hit.t);
+        let hit_right = self.right.hit(ray, *ray_t.start()..=closest);
+        hit_right.or(hit_left)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Sphere = crate::sphere::Sphere<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use super::*;
+    use crate::lambertian::Lambertian;
+
+    fn spheres() -> HittableList<f32> {
+        let mut list: HittableList<f32> = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                list.push(Arc::new(Sphere::new(
+                    Vec3::new(i as f32 - 5.0, j as f32 - 5.0, -10.0 - (i + j) as f32 * 0.1),
+                    0.3 + 0.01 * i as f32,
+                    Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+                )));
+            }
+        }
+        list
+    }
+
+    #[test]
+    fn empty_bvh() {
+        let bvh = BvhNode::<f32>::new(Vec::new());
+        let ray = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]);
+        assert!(bvh.hit(&ray, 0.0..=f32::MAX).is_none());
+        assert!(bvh.bounding_box().is_empty());
+    }
+
+    #[test]
+    fn bvh_matches_linear_list() {
+        let list = spheres();
+        let bvh = BvhNode::new(spheres());
+        assert_eq!(bvh.bounding_box(), list.bounding_box());
+        for x in -30..30 {
+            for y in -30..30 {
+                let ray = Ray::new([0.0, 0.0, 0.0], [x as f32 * 0.03, y as f32 * 0.03, -1.0]);
+                let expected = list.hit(&ray, 0.001..=f32::MAX).map(|hit| hit.t);
+                let got = bvh.hit(&ray, 0.001..=f32::MAX).map(|hit| hit.t);
+                assert_eq!(expected, got);
+            }
+        }
+    }
+}
@@ -1,47 +1,218 @@
+use crate::background::Background;
 use crate::color::Color;
 use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::image::Image;
 use crate::ray::Ray;
+use crate::rng::{self, Rng};
 use crate::vec3::{Point3, Vec3};
 use crate::velem::VElem;
 use rand::distributions::{Distribution, Uniform};
-use std::io::{stderr, stdout, Write};
+use rand::Rng as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
 
 pub struct Camera<T: VElem> {
     samples_per_pixel: u16,
     max_depth: u16,
+    threads: usize,
+    seed: u64,
     samples_scale: T,
     rand_distr: Uniform<T>,
-    aspect_ratio: T,
     image_width: u64,
     image_height: u64,
     center: Point3<T>,
     pixel00_loc: Point3<T>,
     pixel_delta_u: Vec3<T>,
     pixel_delta_v: Vec3<T>,
+    defocus_angle: T,
+    defocus_disk_u: Vec3<T>,
+    defocus_disk_v: Vec3<T>,
+    shutter: (T, T),
+    background: Background<T>,
+    lights: HittableList<T>,
 }
 
-impl<T: VElem> Camera<T> {
-    pub fn new(
-        aspect_ratio: (u8, u8),
-        image_width: u64,
-        samples_per_pixel: u16,
-        max_depth: u16,
-    ) -> Self {
-        let aspect_ratio = aspect_ratio.0 as f32 / aspect_ratio.1 as f32;
-        let image_height = (image_width as f32 / aspect_ratio) as u64;
-        assert!(image_height > 1);
+/// Collects the camera parameters and derives the viewport from them in [`CameraBuilder::build`].
+/// The defaults reproduce the original fixed camera: eye at the origin looking down -Z with a
+/// 90 degree vertical field of view.
+pub struct CameraBuilder<T: VElem> {
+    aspect_ratio: (u8, u8),
+    image_width: u64,
+    image_height: Option<u64>,
+    samples_per_pixel: u16,
+    max_depth: u16,
+    threads: usize,
+    seed: u64,
+    vfov: T,
+    look_from: Point3<T>,
+    look_at: Point3<T>,
+    vup: Vec3<T>,
+    defocus_angle: T,
+    focus_dist: Option<T>,
+    shutter: (T, T),
+    background: Background<T>,
+    lights: HittableList<T>,
+}
+
+impl<T: VElem> Default for CameraBuilder<T> {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: (16, 9),
+            image_width: 400,
+            image_height: None,
+            samples_per_pixel: 100,
+            max_depth: 10,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: 0,
+            vfov: 90.0.into(),
+            look_from: Point3::default(),
+            look_at: Point3::new(T::zero(), T::zero(), -T::one()),
+            vup: Vec3::new(T::zero(), T::one(), T::zero()),
+            defocus_angle: T::zero(),
+            focus_dist: None,
+            shutter: (T::zero(), T::one()),
+            background: Background::default(),
+            lights: Vec::new(),
+        }
+    }
+}
+
+impl<T: VElem> CameraBuilder<T> {
+    pub fn aspect_ratio(mut self, aspect_ratio: (u8, u8)) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    pub fn image_width(mut self, image_width: u64) -> Self {
+        self.image_width = image_width;
+        self
+    }
+
+    /// fixes the image height instead of deriving it from the width and the aspect ratio
+    pub fn image_height(mut self, image_height: u64) -> Self {
+        self.image_height = Some(image_height);
+        self
+    }
+
+    pub fn samples_per_pixel(mut self, samples_per_pixel: u16) -> Self {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: u16) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// number of worker threads used by [`Camera::render`], defaults to the available parallelism
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// seed for all random sampling, the same seed renders the same image for any thread count
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// vertical field of view in degrees
+    pub fn vfov(mut self, vfov: T) -> Self {
+        self.vfov = vfov;
+        self
+    }
+
+    /// point the camera is looking from
+    pub fn look_from(mut self, look_from: Point3<T>) -> Self {
+        self.look_from = look_from;
+        self
+    }
+
+    /// point the camera is looking at
+    pub fn look_at(mut self, look_at: Point3<T>) -> Self {
+        self.look_at = look_at;
+        self
+    }
+
+    /// camera-relative "up" direction
+    pub fn vup(mut self, vup: Vec3<T>) -> Self {
+        self.vup = vup;
+        self
+    }
+
+    /// variation angle of rays through each pixel in degrees, 0 disables defocus blur
+    pub fn defocus_angle(mut self, defocus_angle: T) -> Self {
+        self.defocus_angle = defocus_angle;
+        self
+    }
+
+    /// distance from the camera to the plane of perfect focus,
+    /// defaults to the distance between look_from and look_at
+    pub fn focus_dist(mut self, focus_dist: T) -> Self {
+        self.focus_dist = Some(focus_dist);
+        self
+    }
+
+    /// Times the shutter opens and closes, every ray is sent at a random time in between.
+    /// Moving objects travel from time 0 to 1, the default shutter covers all of that, and
+    /// both times are clamped to that range. An instant shutter with `open == close` freezes
+    /// the motion.
+    pub fn shutter(mut self, open: T, close: T) -> Self {
+        let open = open.max(T::zero()).min(T::one());
+        self.shutter = (open, close.max(open).min(T::one()));
+        self
+    }
+
+    /// what rays that escape the scene see, defaults to [`Background::sky`]
+    pub fn background(mut self, background: Background<T>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Light sources that diffuse surfaces aim part of their samples at, which cuts the noise
+    /// from small lights. They still have to be part of the world. Only quads and spheres can
+    /// be sampled, any other emitter just lights the scene when it is hit by chance.
+    pub fn lights(mut self, lights: HittableList<T>) -> Self {
+        self.lights = lights;
+        self
+    }
+
+    /// Width and height of the rendered image, the height is derived from the width and the
+    /// aspect ratio unless it was given. [`CameraBuilder::build`] needs at least two rows.
+    pub fn image_size(&self) -> (u64, u64) {
+        let aspect_ratio = self.aspect_ratio.0 as f32 / self.aspect_ratio.1 as f32;
+        let image_height = self
+            .image_height
+            .unwrap_or((self.image_width as f32 / aspect_ratio) as u64);
+        (self.image_width, image_height)
+    }
+
+    pub fn build(self) -> Camera<T> {
+        let (image_width, image_height) = self.image_size();
+        assert!(image_height > 1, "image needs at least two rows");
 
         // Camera
-        let focal_length = 1.0;
-        let viewport_height: T = 2.0.into();
+        let camera_center = self.look_from;
+        let focus_dist = self
+            .focus_dist
+            .unwrap_or_else(|| (self.look_from - self.look_at).length());
+        let theta = self.vfov.to_radians();
+        let h = (theta / Into::<T>::into(2.0)).tan();
+        let viewport_height: T = Into::<T>::into(2.0) * h * focus_dist;
         let viewport_width: T = viewport_height * (image_width as f32 / image_height as f32).into();
-        let camera_center = Point3::<T>::default();
+
+        // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
+        // w points away from the scene, u to the camera right and v to the camera up
+        let w = (self.look_from - self.look_at).unit_vector();
+        let u = self.vup.cross(&w).unit_vector();
+        let v = w.cross(&u);
 
         // Calculate the vectors across the horizontal and down the vertical viewport edges.
         // veiwport_u x_min -> x_max
-        let viewport_u = Vec3::<T>::new(viewport_width, T::zero(), T::zero());
+        let viewport_u = u * viewport_width;
         // viewport_v y_max -> y_min
-        let viewport_v = Vec3::<T>::new(T::zero(), -viewport_height, T::zero());
+        let viewport_v = -v * viewport_height;
 
         // Calculate the horizontal and vertical delta vectors from pixel to pixel.
         // pixel_delta_u -> pixel step horizontal for the viewport
@@ -51,7 +222,7 @@ impl<T: VElem> Camera<T> {
 
         // Calculate the location of the upper left pixel.
         let viewport_upper_left = camera_center
-            - Vec3::new(0.0.into(), 0.0.into(), focal_length.into())
+            - w * focus_dist
             - viewport_u / Into::<T>::into(2.0)
             - viewport_v / Into::<T>::into(2.0);
 
@@ -60,70 +231,148 @@ impl<T: VElem> Camera<T> {
         let pixel00_loc =
             viewport_upper_left + (pixel_delta_u + pixel_delta_v) * Into::<T>::into(0.5);
 
-        Self {
-            samples_per_pixel,
-            max_depth,
-            samples_scale: (1.0 / samples_per_pixel as f32).into(),
+        // Calculate the camera defocus disk basis vectors.
+        let defocus_radius = focus_dist
+            * (self.defocus_angle / Into::<T>::into(2.0))
+                .to_radians()
+                .tan();
+        let defocus_disk_u = u * defocus_radius;
+        let defocus_disk_v = v * defocus_radius;
+
+        Camera {
+            samples_per_pixel: self.samples_per_pixel,
+            max_depth: self.max_depth,
+            threads: self.threads,
+            seed: self.seed,
+            samples_scale: (1.0 / self.samples_per_pixel as f32).into(),
             rand_distr: Uniform::<T>::new_inclusive(Into::<T>::into(-0.5), Into::<T>::into(0.5)),
-            aspect_ratio: aspect_ratio.into(),
             image_width,
             image_height,
             center: camera_center,
             pixel_delta_v,
             pixel_delta_u,
             pixel00_loc,
+            defocus_angle: self.defocus_angle,
+            defocus_disk_u,
+            defocus_disk_v,
+            shutter: self.shutter,
+            background: self.background,
+            lights: self.lights,
         }
     }
+}
+
+impl<T: VElem> Camera<T> {
+    pub fn builder() -> CameraBuilder<T> {
+        CameraBuilder::default()
+    }
+
+    /// Renders the image with scanlines handed out to `threads` workers on demand.
+    /// Finished lines are collected into the framebuffer in order once every worker is done,
+    /// so the result does not depend on the scheduling.
+    pub fn render(&self, world: &impl Hittable<T>) -> Image<T> {
+        self.render_with_progress(world, |_, _| {})
+    }
+
+    /// Same as [`Camera::render`] but calls `progress` with the number of finished and total
+    /// scanlines every time a worker completes one.
+    pub fn render_with_progress<F>(&self, world: &impl Hittable<T>, progress: F) -> Image<T>
+    where
+        F: Fn(u64, u64) + Sync,
+    {
+        let next_line = AtomicU64::new(0);
+        let lines_done = AtomicU64::new(0);
+        let mut lines: Vec<Vec<Color<T>>> = vec![Vec::new(); self.image_height as usize];
+
+        thread::scope(|s| {
+            let workers: Vec<_> = (0..self.threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut rendered = Vec::new();
+                        loop {
+                            let y = next_line.fetch_add(1, Ordering::Relaxed);
+                            if y >= self.image_height {
+                                break rendered;
+                            }
+                            rendered.push((y, self.render_line(world, y)));
+                            let done = lines_done.fetch_add(1, Ordering::Relaxed) + 1;
+                            progress(done, self.image_height);
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (y, line) in worker.join().expect("render worker panicked") {
+                    lines[y as usize] = line;
+                }
+            }
+        });
 
-    pub fn render(&self, world: &impl Hittable<T>) {
-        // stdout writer lock
-        let mut lock = stdout().lock();
-        // stderr writer lock
-        let mut err = stderr().lock();
-        write!(
-            lock,
-            "P3\n{} {}\n255\n",
-            self.image_width, self.image_height
+        Image::from_pixels(
+            self.image_width,
+            self.image_height,
+            lines.into_iter().flatten().collect(),
         )
-        .unwrap();
-        for y in 0..self.image_height {
-            write!(err, "\rLines remaining: {}  ", self.image_height - y).unwrap();
-            let _ = err.flush();
-            for x in 0..self.image_width {
+    }
+
+    fn render_line(&self, world: &impl Hittable<T>, y: u64) -> Vec<Color<T>> {
+        (0..self.image_width)
+            .map(|x| {
                 let mut color = Color::<T>::default();
+                let mut rng = rng::pixel_rng(self.seed, self.image_width, x, y);
                 for _ in 0..self.samples_per_pixel {
-                    let r = self.get_ray(x, y);
-                    color += r.color(world, self.max_depth);
+                    let r = self.get_ray(x, y, &mut rng);
+                    color += r.color(
+                        world,
+                        self.max_depth,
+                        &self.background,
+                        &self.lights,
+                        &mut rng,
+                    );
                 }
-                color *= self.samples_scale;
-                color.write_color(&mut lock).unwrap();
-            }
-        }
-        write!(err, "\r Done                          \n").unwrap();
+                color * self.samples_scale
+            })
+            .collect()
     }
 
-    fn get_ray(&self, x: u64, y: u64) -> Ray<T> {
-        // Construct a camera ray originating from the origin and directed at randomly sampled
-        // point around the pixel location i, j.
+    fn get_ray(&self, x: u64, y: u64, rng: &mut Rng) -> Ray<T> {
+        // Construct a camera ray originating from the defocus disk and directed at a randomly
+        // sampled point around the pixel location i, j.
         let x: T = (x as f32).into();
         let y: T = (y as f32).into();
 
-        let offset = self.sample_square();
+        let offset = self.sample_square(rng);
         let pixel_sample = self.pixel00_loc
             + (self.pixel_delta_u * (x + offset.x()))
             + (self.pixel_delta_v * (y + offset.y()));
 
-        let ray_origin = self.center;
+        let ray_origin = if self.defocus_angle <= T::zero() {
+            self.center
+        } else {
+            self.defocus_disk_sample(rng)
+        };
         let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
+        let (open, close) = self.shutter;
+        let ray_time = if open < close {
+            rng.gen_range(open..close)
+        } else {
+            open
+        };
+
+        Ray::with_time(ray_origin, ray_direction, ray_time)
+    }
+
+    /// Returns a random point in the camera defocus disk.
+    fn defocus_disk_sample(&self, rng: &mut Rng) -> Point3<T> {
+        let p = Vec3::<T>::random_in_unit_disk(rng);
+        self.center + (self.defocus_disk_u * p.x()) + (self.defocus_disk_v * p.y())
     }
 
-    fn sample_square(&self) -> Vec3<T> {
-        let mut rng = rand::thread_rng();
+    fn sample_square(&self, rng: &mut Rng) -> Vec3<T> {
         Vec3::new(
-            self.rand_distr.sample(&mut rng),
-            self.rand_distr.sample(&mut rng),
+            self.rand_distr.sample(rng),
+            self.rand_distr.sample(rng),
             T::zero(),
         )
     }
@@ -136,7 +385,12 @@ mod impl_tests {
 
     #[test]
     fn new_test() {
-        let c = Camera::<f32>::new((1, 2), 100, 10, 10);
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((1, 2))
+            .image_width(100)
+            .samples_per_pixel(10)
+            .max_depth(10)
+            .build();
         // height as provided
         assert_eq!(c.image_width, 100);
         // width aspect ratio x the height
@@ -145,4 +399,152 @@ mod impl_tests {
         assert_eq!(c.samples_per_pixel, 10);
         assert_eq!(c.pixel00_loc, Point3::new(-0.495, 0.995, -1.0))
     }
+
+    #[test]
+    fn image_height_test() {
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((16, 9))
+            .image_width(100)
+            .image_height(50)
+            .build();
+        // explicit height wins over the aspect ratio
+        assert_eq!((c.image_width, c.image_height), (100, 50));
+        let builder = Camera::<f32>::builder()
+            .aspect_ratio((16, 9))
+            .image_width(3);
+        assert_eq!(builder.image_size(), (3, 1));
+        // square pixels, the viewport follows the image shape
+        assert!((c.pixel_delta_u.length() - c.pixel_delta_v.length()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn look_at_test() {
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((1, 1))
+            .image_width(100)
+            .look_from(Point3::new(0.0, 0.0, 0.0))
+            .look_at(Point3::new(2.0, 0.0, 0.0))
+            .vfov(90.0)
+            .build();
+        assert_eq!(c.center, Point3::new(0.0, 0.0, 0.0));
+        // focal plane sits at the look at distance, with u pointing to +z and v to +y
+        let expected = Point3::new(2.0, 1.98, -1.98);
+        assert!((c.pixel00_loc - expected).length() < 1e-5);
+        assert!((c.pixel_delta_u - Vec3::new(0.0, 0.0, 0.04)).length() < 1e-6);
+        assert!((c.pixel_delta_v - Vec3::new(0.0, -0.04, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn defocus_test() {
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((1, 1))
+            .image_width(100)
+            .look_from(Point3::new(0.0, 0.0, 0.0))
+            .look_at(Point3::new(0.0, 0.0, -1.0))
+            .focus_dist(10.0)
+            .defocus_angle(10.0)
+            .build();
+        // the viewport is scaled out onto the focus plane
+        assert!((c.pixel00_loc.z() + 10.0).abs() < 1e-5);
+        let radius = 10.0 * 5.0f32.to_radians().tan();
+        assert!((c.defocus_disk_u.length() - radius).abs() < 1e-5);
+        assert!((c.defocus_disk_v.length() - radius).abs() < 1e-5);
+        let mut rng = rng::pixel_rng(0, c.image_width, 50, 50);
+        for _ in 0..100 {
+            let origin = c.get_ray(50, 50, &mut rng).origin();
+            assert!(origin.length() <= radius + 1e-5);
+            assert_eq!(origin.z(), 0.0);
+        }
+    }
+
+    #[test]
+    fn render_test() {
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((2, 1))
+            .image_width(8)
+            .samples_per_pixel(1)
+            .threads(3)
+            .build();
+        let world: crate::hittable_list::HittableList<f32> = Vec::new();
+        let lines = std::sync::Mutex::new(Vec::new());
+        let img = c.render_with_progress(&world, |done, total| {
+            lines.lock().unwrap().push((done, total))
+        });
+        assert_eq!(img.width(), 8);
+        assert_eq!(img.height(), 4);
+        let mut lines = lines.into_inner().unwrap();
+        lines.sort();
+        assert_eq!(lines, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+        // with nothing to hit the top row is bluer than the bottom one
+        assert!(img[(0, 0)].x() < img[(0, 3)].x());
+    }
+
+    #[test]
+    fn background_test() {
+        let c = Camera::<f32>::builder()
+            .aspect_ratio((2, 1))
+            .image_width(4)
+            .samples_per_pixel(1)
+            .background(Background::Solid(Color::new(0.25, 0.5, 1.0)))
+            .build();
+        let world: crate::hittable_list::HittableList<f32> = Vec::new();
+        let img = c.render(&world);
+        assert!(img
+            .pixels()
+            .iter()
+            .all(|&p| p == Color::new(0.25, 0.5, 1.0)));
+    }
+
+    #[test]
+    fn seed_test() {
+        use crate::lambertian::Lambertian;
+        use crate::sphere::Sphere;
+        use std::sync::Arc;
+
+        let world: crate::hittable_list::HittableList<f32> = vec![Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -1.0),
+            0.5,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        ))];
+        let render = |seed, threads| {
+            Camera::<f32>::builder()
+                .aspect_ratio((1, 1))
+                .image_width(8)
+                .samples_per_pixel(4)
+                .defocus_angle(2.0)
+                .seed(seed)
+                .threads(threads)
+                .build()
+                .render(&world)
+        };
+        let image = render(7, 1);
+        assert_eq!(image.pixels(), render(7, 4).pixels());
+        assert_ne!(image.pixels(), render(8, 1).pixels());
+    }
+
+    #[test]
+    fn shutter_test() {
+        let c = Camera::<f32>::builder().shutter(0.25, 0.5).build();
+        let mut rng = rng::pixel_rng(0, c.image_width, 0, 0);
+        let times: Vec<_> = (0..100).map(|_| c.get_ray(0, 0, &mut rng).time()).collect();
+        assert!(times.iter().all(|t| (0.25..0.5).contains(t)));
+        assert!(times.iter().any(|t| *t != times[0]));
+
+        let c = Camera::<f32>::builder().shutter(0.5, 0.5).build();
+        assert_eq!(c.get_ray(0, 0, &mut rng).time(), 0.5);
+
+        // moving objects only exist between time 0 and 1
+        let c = Camera::<f32>::builder().shutter(3.0, 3.0).build();
+        assert_eq!(c.shutter, (1.0, 1.0));
+        let c = Camera::<f32>::builder().shutter(-1.0, 0.5).build();
+        assert_eq!(c.shutter, (0.0, 0.5));
+    }
+
+    #[test]
+    fn threads_test() {
+        let c = Camera::<f32>::builder().threads(0).build();
+        assert_eq!(c.threads, 1);
+        let c = Camera::<f32>::builder().threads(32).build();
+        assert_eq!(c.threads, 32);
+    }
 }
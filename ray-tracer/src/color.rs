@@ -5,30 +5,51 @@ use std::io::Write;
 pub type Color<T> = Vec3<T>;
 
 impl<T: VElem> Color<T> {
+    /// piecewise sRGB transfer function (OETF), maps display linear [0, 1] to encoded [0, 1]
     #[inline]
-    fn to_gamma(linear: T) -> T {
-        if linear >= T::zero() {
-            linear.sqrt()
-        } else {
+    pub fn linear_to_srgb(linear: T) -> T {
+        if linear <= T::zero() {
             T::zero()
+        } else if linear <= 0.0031308.into() {
+            linear * 12.92.into()
+        } else {
+            Into::<T>::into(1.055) * linear.powf((1.0 / 2.4).into()) - 0.055.into()
+        }
+    }
+
+    /// inverse of [`Color::linear_to_srgb`], maps encoded [0, 1] back to display linear [0, 1]
+    #[inline]
+    pub fn srgb_to_linear(encoded: T) -> T {
+        if encoded <= 0.04045.into() {
+            encoded / 12.92.into()
+        } else {
+            ((encoded + 0.055.into()) / 1.055.into()).powf(2.4.into())
         }
     }
 
+    /// sRGB encoded channels quantized to 8 bits
+    pub fn to_rgb8(self) -> [u8; 3] {
+        let intensity: std::ops::RangeInclusive<T> = 0.0.into()..=0.999.into();
+        [self.x(), self.y(), self.z()].map(|c| {
+            let c = Self::linear_to_srgb(c.clamp(*intensity.start(), *intensity.end()));
+            (c * 256.0.into()).trunc().to_u8().unwrap_or(0)
+        })
+    }
+
+    /// sRGB encoded channels quantized to 16 bits
+    pub fn to_rgb16(self) -> [u16; 3] {
+        [self.x(), self.y(), self.z()].map(|c| {
+            let c = Self::linear_to_srgb(c.clamp(T::zero(), T::one()));
+            (c * 65535.0.into()).round().to_u16().unwrap_or(0)
+        })
+    }
+
     pub fn write_color<W>(self, out: &mut W) -> Result<(), std::io::Error>
     where
         W: Write,
     {
-        let intensity: std::ops::RangeInclusive<T> = 0.0.into()..=0.999.into();
-        let r = Self::to_gamma(self.x().clamp(*intensity.start(), *intensity.end()));
-        let g = Self::to_gamma(self.y().clamp(*intensity.start(), *intensity.end()));
-        let b = Self::to_gamma(self.z().clamp(*intensity.start(), *intensity.end()));
-        writeln!(
-            out,
-            "{} {} {}",
-            (r * 256.0.into()).trunc(),
-            (g * 256.0.into()).trunc(),
-            (b * 256.0.into()).trunc(),
-        )
+        let [r, g, b] = self.to_rgb8();
+        writeln!(out, "{} {} {}", r, g, b)
     }
 }
 
@@ -45,6 +66,21 @@ mod color_tests {
             .write_color(&mut s)
             .expect("Should write normally");
         let st = String::from_utf8(s).unwrap();
-        assert_eq!(st, "126 0 252\n");
+        assert_eq!(st, "135 0 252\n");
+    }
+
+    #[test]
+    fn test_quantize() {
+        let v = Color::from([0.25, 2.0, -1.0]);
+        assert_eq!(v.to_rgb8(), [137, 255, 0]);
+        assert_eq!(v.to_rgb16(), [35199, 65535, 0]);
+    }
+
+    #[test]
+    fn test_srgb_round_trip() {
+        for c in [0.0f32, 0.002, 0.18, 0.5, 1.0] {
+            let back = Color::srgb_to_linear(Color::linear_to_srgb(c));
+            assert!((back - c).abs() < 1e-6, "{} came back as {}", c, back);
+        }
     }
 }
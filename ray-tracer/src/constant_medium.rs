@@ -0,0 +1,216 @@
+use crate::aabb::Aabb;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::isotropic::Isotropic;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::velem::VElem;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Volume of uniform density filling a closed `boundary`, like fog or smoke.
+/// A ray passing through is scattered after an exponentially distributed distance, or not at
+/// all if that distance lies beyond the far side of the boundary. The boundary has to be
+/// convex, as only the first stretch of the ray inside it is considered.
+pub struct ConstantMedium<T: VElem> {
+    boundary: Arc<dyn Hittable<T>>,
+    neg_inv_density: T,
+    phase_function: Arc<dyn Material<T>>,
+    /// hash of where the medium is and how dense, mixed into the draw so that media crossed
+    /// by the same ray scatter independently
+    salt: u64,
+}
+
+impl<T: VElem> ConstantMedium<T> {
+    pub fn new(boundary: Arc<dyn Hittable<T>>, density: T, albedo: Color<T>) -> Self {
+        Self::with_phase_function(boundary, density, Isotropic::new(albedo))
+    }
+
+    pub fn textured(
+        boundary: Arc<dyn Hittable<T>>,
+        density: T,
+        albedo: impl Texture<T> + 'static,
+    ) -> Self {
+        Self::with_phase_function(boundary, density, Isotropic::textured(albedo))
+    }
+
+    fn with_phase_function(
+        boundary: Arc<dyn Hittable<T>>,
+        density: T,
+        phase_function: impl Material<T> + 'static,
+    ) -> Self {
+        assert!(density > T::zero(), "medium density must be positive");
+        let bbox = boundary.bounding_box();
+        let (c, e) = (bbox.centroid(), bbox.extent());
+        let salt = [c.x(), c.y(), c.z(), e.x(), e.y(), e.z(), density]
+            .map(|v| v.to_f64().unwrap_or(0.0).to_bits());
+        Self {
+            boundary,
+            neg_inv_density: -T::one() / density,
+            phase_function: Arc::new(phase_function),
+            salt: rng::hash_unit(&salt).to_bits(),
+        }
+    }
+
+    /// uniform number in (0, 1] drawn from the random bits of the ray, which [`Ray::color`]
+    /// takes from the path's generator
+    fn sample(&self, ray: &Ray<T>) -> T {
+        (1.0 - rng::hash_unit(&[ray.random(), self.salt]) as f32).into()
+    }
+}
+
+impl<T: VElem> Hittable<T> for ConstantMedium<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        // where the whole line enters and leaves the boundary, the ray may start inside
+        let enter = self.boundary.hit(ray, -T::infinity()..=T::infinity())?.t;
+        let exit = self
+            .boundary
+            .hit(ray, enter + 0.0001.into()..=T::infinity())?
+            .t;
+
+        let t_enter = enter.max(*ray_t.start()).max(T::zero());
+        let t_exit = exit.min(*ray_t.end());
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction().length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * self.sample(ray).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        Some(HitRecord {
+            p: ray.at(t),
+            // the normal and facing are meaningless inside a volume
+            normal: Vec3::new(T::one(), T::zero(), T::zero()),
+            t,
+            u: T::zero(),
+            v: T::zero(),
+            front_facing: true,
+            color: None,
+            material: self.phase_function.clone(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Sphere = crate::sphere::Sphere<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use super::ConstantMedium;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+    use std::sync::Arc;
+
+    fn fog(density: f32) -> ConstantMedium<f32> {
+        let boundary = Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, -5.0),
+            1.0,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        ));
+        ConstantMedium::new(boundary, density, Color::new(1.0, 1.0, 1.0))
+    }
+
+    fn ray(i: usize) -> Ray {
+        // rays through the center with different random bits
+        Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -5.0)).with_random(i as u64)
+    }
+
+    #[test]
+    fn hits_stay_inside() {
+        let medium = fog(2.0);
+        for i in 0..100 {
+            if let Some(hit) = medium.hit(&ray(i), 0.0..=100.0) {
+                assert!((hit.p - Vec3::new(0.0, 0.0, -5.0)).length() <= 1.0 + 1e-4);
+            }
+        }
+        // rays missing the boundary never scatter
+        let miss = Ray::new(Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(medium.hit(&miss, 0.0..=100.0).is_none());
+        assert_eq!(medium.bounding_box().extent(), Vec3::new(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn density_sets_how_often_rays_scatter() {
+        // the chance to pass the diameter of 2 is exp(-2 * density)
+        let scattered = |density| {
+            let medium = fog(density);
+            (0..2000)
+                .filter(|i| medium.hit(&ray(*i), 0.0..=100.0).is_some())
+                .count() as f32
+                / 2000.0
+        };
+        assert!((scattered(0.1) - (1.0 - (-0.2f32).exp())).abs() < 0.05);
+        assert!((scattered(1.0) - (1.0 - (-2.0f32).exp())).abs() < 0.05);
+        assert!(scattered(50.0) > 0.99);
+    }
+
+    #[test]
+    fn media_scatter_independently() {
+        // two unit fogs one behind the other, each passed with a chance of exp(-2 * 0.5)
+        let fog_at = |z| {
+            let boundary = Arc::new(Sphere::new(
+                Vec3::new(0.0, 0.0, z),
+                1.0,
+                Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+            ));
+            Arc::new(ConstantMedium::new(
+                boundary,
+                0.5,
+                Color::new(1.0, 1.0, 1.0),
+            ))
+        };
+        let world: crate::hittable_list::HittableList<f32> = vec![fog_at(-5.0), fog_at(-10.0)];
+        let ray = |i: usize| {
+            Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)).with_random(i as u64)
+        };
+        let passed = (0..2000)
+            .filter(|i| world.hit(&ray(*i), 0.0..=100.0).is_none())
+            .count() as f32
+            / 2000.0;
+        assert!((passed - (-2.0f32).exp()).abs() < 0.05, "{}", passed);
+    }
+
+    #[test]
+    fn renders_are_reproducible() {
+        // dense fog in front of the sky, rendered from the camera seed alone
+        let render = |seed| {
+            let world: crate::hittable_list::HittableList<f32> = vec![Arc::new(fog(0.5))];
+            crate::camera::Camera::builder()
+                .aspect_ratio((1, 1))
+                .image_width(8)
+                .samples_per_pixel(4)
+                .threads(3)
+                .seed(seed)
+                .build()
+                .render(&world)
+        };
+        let first = render(1);
+        // media created in between do not change the next render
+        let _others = [fog(1.0), fog(2.0)];
+        assert_eq!(render(1), first);
+        assert_ne!(render(2), first);
+    }
+
+    #[test]
+    fn ray_starting_inside() {
+        let medium = fog(50.0);
+        let inside = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 1.0, 0.0));
+        let hit = medium.hit(&inside, 0.0..=100.0).unwrap();
+        assert!(hit.t >= 0.0 && hit.t <= 1.0);
+        // the same ray always scatters at the same spot
+        assert_eq!(medium.hit(&inside, 0.0..=100.0).unwrap().t, hit.t);
+    }
+}
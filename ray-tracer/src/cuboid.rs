@@ -0,0 +1,123 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::quad::Quad;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Axis aligned box made of six [`Quad`] faces sharing one material, all facing outwards.
+/// Rotated or moved boxes are placed with an [`crate::instance::Instance`].
+pub struct Cuboid<T: VElem> {
+    sides: HittableList<T>,
+    bbox: Aabb<T>,
+}
+
+impl<T: VElem> Cuboid<T> {
+    /// box between the opposite corners `a` and `b`, it must have some extent along every axis
+    pub fn new(a: Point3<T>, b: Point3<T>, material: impl Material<T> + 'static) -> Self {
+        let min = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+        assert!(
+            (0..3).all(|axis| min[axis] < max[axis]),
+            "flat box, use a quad instead"
+        );
+        let dx = Vec3::new(max.x() - min.x(), T::zero(), T::zero());
+        let dy = Vec3::new(T::zero(), max.y() - min.y(), T::zero());
+        let dz = Vec3::new(T::zero(), T::zero(), max.z() - min.z());
+
+        let material: Arc<dyn Material<T>> = Arc::new(material);
+        // every face is spanned so that u x v points out of the box
+        let faces = [
+            (Point3::new(min.x(), min.y(), max.z()), dx, dy), // front
+            (Point3::new(max.x(), min.y(), max.z()), -dz, dy), // right
+            (Point3::new(max.x(), min.y(), min.z()), -dx, dy), // back
+            (Point3::new(min.x(), min.y(), min.z()), dz, dy), // left
+            (Point3::new(min.x(), max.y(), max.z()), dx, -dz), // top
+            (Point3::new(min.x(), min.y(), min.z()), dx, dz), // bottom
+        ];
+        let mut sides: HittableList<T> = Vec::with_capacity(faces.len());
+        for (q, u, v) in faces {
+            sides.push(Arc::new(Quad::new(q, u, v, material.clone())));
+        }
+        let bbox = sides.bounding_box();
+        Self { sides, bbox }
+    }
+}
+
+impl<T: VElem> Hittable<T> for Cuboid<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        self.sides.hit(ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use super::Cuboid;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+
+    fn unit_box() -> Cuboid<f32> {
+        Cuboid::new(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(-1.0, -1.0, -1.0),
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    #[test]
+    fn outward_normals() {
+        let cube = unit_box();
+        for axis in 0..3 {
+            for sign in [-1.0, 1.0] {
+                let mut outside = [0.0, 0.2, 0.3];
+                outside[axis] = 5.0 * sign;
+                let origin = Vec3::from(outside);
+                let mut towards = [0.0; 3];
+                towards[axis] = -sign;
+                let ray = Ray::new(origin, Vec3::from(towards));
+                let hit = cube.hit(&ray, 0.0..=10.0).expect("ray should hit the box");
+                assert!((hit.t - 4.0).abs() < 1e-6);
+                assert!(hit.front_facing, "face {} {} points inwards", axis, sign);
+                assert_eq!(hit.normal, -Vec3::from(towards));
+            }
+        }
+    }
+
+    #[test]
+    fn ray_from_inside() {
+        let cube = unit_box();
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let hit = cube.hit(&ray, 0.0..=10.0).unwrap();
+        assert_eq!(hit.t, 1.0);
+        assert!(!hit.front_facing);
+        assert_eq!(hit.normal, Vec3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bbox = unit_box().bounding_box();
+        assert!((bbox.extent() - Vec3::new(2.0, 2.0, 2.0)).length() < 1e-3);
+        assert_eq!(bbox.centroid(), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn flat_box() {
+        Cuboid::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 1.0),
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        );
+    }
+}
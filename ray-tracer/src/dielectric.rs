@@ -1,5 +1,5 @@
 use crate::color::Color;
-use crate::material::Material;
+use crate::material::{Material, ScatterRecord, Scattered};
 use crate::ray::Ray;
 use crate::velem::VElem;
 use rand::Rng;
@@ -18,7 +18,7 @@ impl<T: VElem> Dielectric<T> {
         // Use Schlick's approximation for reflectance.
         let mut r0 = (T::one() - refraction_index) / (T::one() + refraction_index);
         r0 = r0 * r0;
-        return r0 + (T::one() - r0) * (T::one() - cos).powf(5.0.into());
+        r0 + (T::one() - r0) * (T::one() - cos).powf(5.0.into())
     }
 }
 
@@ -27,7 +27,8 @@ impl<T: VElem> Material<T> for Dielectric<T> {
         &self,
         ray_in: &crate::ray::Ray<T>,
         hit: &crate::hittable::HitRecord<T>,
-    ) -> Option<(crate::ray::Ray<T>, crate::color::Color<T>)> {
+        rng: &mut crate::rng::Rng,
+    ) -> Option<ScatterRecord<T>> {
         let attenuation = Color::from([T::one(); 3]);
         let ri = if hit.front_facing {
             T::one() / self.refraction_index
@@ -40,13 +41,16 @@ impl<T: VElem> Material<T> for Dielectric<T> {
         let sin_theta = T::sqrt(T::one() - cos_theta * cos_theta);
 
         let direction = if ri * sin_theta > T::one()
-            || Self::reflectance(cos_theta, ri) > rand::thread_rng().gen_range(0.0..=1.0).into()
+            || Self::reflectance(cos_theta, ri) > rng.gen_range(0.0..=1.0).into()
         {
             unit_dir.reflect(hit.normal)
         } else {
             unit_dir.refract(hit.normal, ri)
         };
 
-        Some((Ray::new(hit.p, direction), attenuation))
+        Some(ScatterRecord {
+            attenuation,
+            scattered: Scattered::Specular(Ray::with_time(hit.p, direction, ray_in.time())),
+        })
     }
 }
@@ -0,0 +1,30 @@
+use crate::color::Color;
+use crate::material::{Material, ScatterRecord};
+use crate::vec3::Point3;
+use crate::velem::VElem;
+
+/// Light emitting material, it does not scatter incoming rays
+pub struct DiffuseLight<T: VElem> {
+    emit: Color<T>,
+}
+
+impl<T: VElem> DiffuseLight<T> {
+    pub fn new(emit: Color<T>) -> Self {
+        Self { emit }
+    }
+}
+
+impl<T: VElem> Material<T> for DiffuseLight<T> {
+    fn scatter(
+        &self,
+        _ray_in: &crate::ray::Ray<T>,
+        _hit: &crate::hittable::HitRecord<T>,
+        _rng: &mut crate::rng::Rng,
+    ) -> Option<ScatterRecord<T>> {
+        None
+    }
+
+    fn emitted(&self, _u: T, _v: T, _p: Point3<T>) -> Color<T> {
+        self.emit
+    }
+}
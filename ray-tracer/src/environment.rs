@@ -0,0 +1,317 @@
+use crate::color::Color;
+use crate::image::Image;
+use crate::pdf::Pdf;
+use crate::rng::Rng;
+use crate::vec3::Vec3;
+use crate::velem::VElem;
+use rand::Rng as _;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+/// Piecewise constant distribution over [0, 1) with one bucket per entry of `func`
+#[derive(Clone, Debug, PartialEq)]
+struct Distribution1D<T: VElem> {
+    func: Vec<T>,
+    cdf: Vec<T>,
+    integral: T,
+}
+
+impl<T: VElem> Distribution1D<T> {
+    fn new(func: Vec<T>) -> Self {
+        let n: T = (func.len() as f32).into();
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(T::zero());
+        for (i, f) in func.iter().enumerate() {
+            cdf.push(cdf[i] + *f / n);
+        }
+        let integral = cdf[func.len()];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            // an all black function falls back to a uniform distribution
+            *c = if integral > T::zero() {
+                *c / integral
+            } else {
+                Into::<T>::into(i as f32) / n
+            };
+        }
+        Self {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// density of the bucket `index`
+    fn pdf(&self, index: usize) -> T {
+        if self.integral > T::zero() {
+            self.func[index] / self.integral
+        } else {
+            T::one()
+        }
+    }
+
+    /// maps a uniform `u` in [0, 1) to (x in [0, 1), density at x, bucket of x)
+    fn sample(&self, u: T) -> (T, T, usize) {
+        let index = (self.cdf.partition_point(|c| *c <= u) - 1).min(self.count() - 1);
+        let width = self.cdf[index + 1] - self.cdf[index];
+        let du = if width > T::zero() {
+            (u - self.cdf[index]) / width
+        } else {
+            T::zero()
+        };
+        let x = (Into::<T>::into(index as f32) + du) / (self.count() as f32).into();
+        (x, self.pdf(index), index)
+    }
+}
+
+/// Equirectangular (latitude / longitude) environment map.
+/// The top row of the image looks straight up (+Y), the bottom row straight down, and the
+/// center column looks down -Z. Bright texels can be importance sampled with
+/// [`EnvironmentMap::sample`], and through the [`Pdf`] implementation [`crate::ray::Ray::color`]
+/// uses the map as a light source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentMap<T: VElem> {
+    image: Image<T>,
+    /// one distribution over the columns of every row
+    conditional: Vec<Distribution1D<T>>,
+    /// distribution over the rows
+    marginal: Distribution1D<T>,
+}
+
+impl<T: VElem> EnvironmentMap<T> {
+    /// fails on an empty image, which has no direction to look anything up in
+    pub fn new(image: Image<T>) -> Result<Self, Error> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(Error::new(ErrorKind::InvalidData, "empty environment map"));
+        }
+        let height: T = (image.height() as f32).into();
+        let conditional: Vec<_> = image
+            .rows()
+            .enumerate()
+            .map(|(y, row)| {
+                // rows near the poles cover less solid angle
+                let theta = T::PI() * (Into::<T>::into(y as f32) + 0.5.into()) / height;
+                let sin_theta = theta.sin();
+                // negative texels from a PFM would make the CDF decrease, they get no samples
+                Distribution1D::new(
+                    row.iter()
+                        .map(|c| luminance(*c).max(T::zero()) * sin_theta)
+                        .collect(),
+                )
+            })
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral).collect());
+        Ok(Self {
+            image,
+            conditional,
+            marginal,
+        })
+    }
+
+    /// loads an equirectangular `.hdr` or `.pfm` image
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(Image::open(path)?)
+    }
+
+    /// radiance arriving from `direction`
+    pub fn radiance(&self, direction: Vec3<T>) -> Color<T> {
+        let (u, v) = direction_to_uv(direction);
+        let (x, y) = self.texel(u, v);
+        self.image[(x as u64, y as u64)]
+    }
+
+    /// Importance samples a direction from two uniform numbers in [0, 1),
+    /// returns the direction and its probability density with respect to solid angle.
+    pub fn sample(&self, u1: T, u2: T) -> (Vec3<T>, T) {
+        let (v, pdf_v, y) = self.marginal.sample(u1);
+        let (u, pdf_u, _) = self.conditional[y].sample(u2);
+        let direction = uv_to_direction(u, v);
+        let sin_theta = (T::PI() * v).sin();
+        let pdf = if sin_theta > T::zero() {
+            pdf_u * pdf_v / (Into::<T>::into(2.0) * T::PI() * T::PI() * sin_theta)
+        } else {
+            T::zero()
+        };
+        (direction, pdf)
+    }
+
+    /// density of sampling `direction` with [`EnvironmentMap::sample`], with respect to solid angle
+    pub fn pdf(&self, direction: Vec3<T>) -> T {
+        let (u, v) = direction_to_uv(direction);
+        let sin_theta = (T::PI() * v).sin();
+        if sin_theta <= T::zero() {
+            return T::zero();
+        }
+        let (x, y) = self.texel(u, v);
+        self.marginal.pdf(y) * self.conditional[y].pdf(x)
+            / (Into::<T>::into(2.0) * T::PI() * T::PI() * sin_theta)
+    }
+
+    fn texel(&self, u: T, v: T) -> (usize, usize) {
+        let w = self.image.width() as usize;
+        let h = self.image.height() as usize;
+        let x = (u * (w as f32).into()).to_usize().unwrap_or(0).min(w - 1);
+        let y = (v * (h as f32).into()).to_usize().unwrap_or(0).min(h - 1);
+        (x, y)
+    }
+}
+
+/// directions drawn towards the bright texels of the map
+impl<T: VElem> Pdf<T> for EnvironmentMap<T> {
+    fn value(&self, direction: Vec3<T>) -> T {
+        self.pdf(direction)
+    }
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T> {
+        let u1: T = rng.gen_range(0.0f32..1.0).into();
+        let u2: T = rng.gen_range(0.0f32..1.0).into();
+        self.sample(u1, u2).0
+    }
+}
+
+/// Rec. 709 relative luminance
+fn luminance<T: VElem>(c: Color<T>) -> T {
+    c.x() * 0.2126.into() + c.y() * 0.7152.into() + c.z() * 0.0722.into()
+}
+
+/// u = phi / 2pi + 0.5 with phi measured around +Y from -Z, v = theta / pi from +Y
+fn direction_to_uv<T: VElem>(direction: Vec3<T>) -> (T, T) {
+    let d = direction.unit_vector();
+    let phi = T::atan2(d.x(), -d.z());
+    let theta = d.y().clamp(-T::one(), T::one()).acos();
+    (phi / T::TAU() + 0.5.into(), theta / T::PI())
+}
+
+fn uv_to_direction<T: VElem>(u: T, v: T) -> Vec3<T> {
+    let phi = (u - 0.5.into()) * T::TAU();
+    let theta = v * T::PI();
+    Vec3::new(
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        -theta.sin() * phi.cos(),
+    )
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    fn map_with_hotspot() -> EnvironmentMap<f32> {
+        let mut image = Image::new(16, 8);
+        for p in image.pixels_mut() {
+            *p = Color::new(0.1, 0.1, 0.1);
+        }
+        image[(5, 3)] = Color::new(1000.0, 1000.0, 1000.0);
+        EnvironmentMap::new(image).unwrap()
+    }
+
+    #[test]
+    fn uv_roundtrip() {
+        for (u, v) in [(0.5f32, 0.5f32), (0.1, 0.2), (0.9, 0.7), (0.25, 0.5)] {
+            let (u2, v2) = direction_to_uv(uv_to_direction(u, v));
+            assert!((u - u2).abs() < 1e-5 && (v - v2).abs() < 1e-5);
+        }
+        // center of the map looks forward, top looks up
+        let forward = uv_to_direction(0.5f32, 0.5);
+        assert!((forward - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6);
+        assert!((uv_to_direction(0.3f32, 0.0) - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn radiance_lookup() {
+        let mut image = Image::new(4, 2);
+        image[(2, 0)] = Color::new(1.0, 2.0, 3.0);
+        let env = EnvironmentMap::new(image).unwrap();
+        // upper hemisphere, u in [0.5, 0.75) -> looking forward and to the right
+        assert_eq!(
+            env.radiance(Vec3::new(0.3, 0.5, -1.0)),
+            Color::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            env.radiance(Vec3::new(0.3, -0.5, -1.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn sampling_prefers_bright_texels() {
+        let env = map_with_hotspot();
+        let n = 32;
+        let mut hits = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let u1 = (i as f32 + 0.5) / n as f32;
+                let u2 = (j as f32 + 0.5) / n as f32;
+                let (direction, pdf) = env.sample(u1, u2);
+                assert!(pdf > 0.0);
+                assert!((direction.length() - 1.0).abs() < 1e-5);
+                // the returned density agrees with the one looked up by direction
+                let lookup = env.pdf(direction);
+                assert!((pdf - lookup).abs() <= pdf * 1e-3, "{} != {}", pdf, lookup);
+                if env.radiance(direction).x() > 1.0 {
+                    hits += 1;
+                }
+            }
+        }
+        assert!(
+            hits > n * n * 9 / 10,
+            "only {} samples hit the hotspot",
+            hits
+        );
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let env = map_with_hotspot();
+        // midpoint rule over (u, v) with the solid angle element 2pi^2 sin(theta)
+        let n = 256;
+        let mut integral = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = (i as f32 + 0.5) / n as f32;
+                let v = (j as f32 + 0.5) / n as f32;
+                let d_omega = 2.0 * std::f32::consts::PI.powi(2) * (std::f32::consts::PI * v).sin()
+                    / (n * n) as f32;
+                integral += env.pdf(uv_to_direction(u, v)) * d_omega;
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.01, "integral = {}", integral);
+    }
+
+    #[test]
+    fn black_map_samples_uniformly() {
+        let env = EnvironmentMap::<f32>::new(Image::new(4, 4)).unwrap();
+        let (_, pdf) = env.sample(0.3, 0.6);
+        assert!(pdf > 0.0);
+    }
+
+    #[test]
+    fn negative_texels_are_never_sampled() {
+        let mut image = Image::new(4, 2);
+        image[(1, 0)] = Color::new(-5.0, -5.0, -5.0);
+        image[(2, 1)] = Color::new(1.0, 1.0, 1.0);
+        let env = EnvironmentMap::new(image).unwrap();
+        let n = 16;
+        for i in 0..n {
+            for j in 0..n {
+                let u1 = (i as f32 + 0.5) / n as f32;
+                let u2 = (j as f32 + 0.5) / n as f32;
+                let (direction, pdf) = env.sample(u1, u2);
+                assert!(pdf > 0.0 && pdf.is_finite());
+                assert_eq!(env.radiance(direction), Color::new(1.0, 1.0, 1.0));
+            }
+        }
+        assert_eq!(env.pdf(uv_to_direction(0.375, 0.25)), 0.0);
+    }
+
+    #[test]
+    fn empty_map() {
+        let err = EnvironmentMap::<f32>::new(Image::new(0, 0)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(EnvironmentMap::<f32>::new(Image::new(4, 0)).is_err());
+    }
+}
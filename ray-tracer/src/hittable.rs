@@ -1,16 +1,24 @@
+use crate::aabb::Aabb;
+use crate::color::Color;
 use crate::material::Material;
 use crate::ray::Ray;
+use crate::rng::Rng;
 use crate::vec3::{Point3, Vec3};
 use crate::velem::VElem;
 use std::ops::RangeInclusive;
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct HitRecord<T: VElem> {
     pub p: Point3<T>,
     pub normal: Vec3<T>,
     pub t: T,
+    /// surface coordinates of the hit point
+    pub u: T,
+    pub v: T,
+    /// vertex color interpolated at the hit point, for meshes that carry them
+    pub color: Option<Color<T>>,
     pub front_facing: bool,
-    pub material: Rc<dyn Material<T>>,
+    pub material: Arc<dyn Material<T>>,
 }
 
 impl<T: VElem> HitRecord<T> {
@@ -24,6 +32,21 @@ impl<T: VElem> HitRecord<T> {
     }
 }
 
-pub trait Hittable<T: VElem> {
+pub trait Hittable<T: VElem>: Send + Sync {
     fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>>;
+
+    /// box enclosing the whole object, used to build the [`crate::bvh::BvhNode`] tree
+    fn bounding_box(&self) -> Aabb<T>;
+
+    /// Density with respect to solid angle of [`Hittable::random`] picking `direction` from
+    /// `origin`, which lets light sources be sampled directly. Objects that can't be sampled
+    /// keep the default of zero.
+    fn pdf_value(&self, _origin: Point3<T>, _direction: Vec3<T>) -> T {
+        T::zero()
+    }
+
+    /// direction from `origin` towards a random point of the object
+    fn random(&self, _origin: Point3<T>, _rng: &mut Rng) -> Vec3<T> {
+        Vec3::new(T::one(), T::zero(), T::zero())
+    }
 }
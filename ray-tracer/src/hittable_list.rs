@@ -1,8 +1,12 @@
+use crate::aabb::Aabb;
 use crate::hittable::Hittable;
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
 use crate::velem::VElem;
-use std::rc::Rc;
+use rand::Rng as _;
+use std::sync::Arc;
 
-pub type HittableList<T> = Vec<Rc<dyn Hittable<T>>>;
+pub type HittableList<T> = Vec<Arc<dyn Hittable<T>>>;
 
 impl<T: VElem> Hittable<T> for HittableList<T> {
     fn hit(
@@ -13,11 +17,30 @@ impl<T: VElem> Hittable<T> for HittableList<T> {
         let mut closest_hit = None;
         let mut closest_hit_time = *ray_t.end();
         for object in self {
-            if let Some(hit) = object.hit(&ray, *ray_t.start()..=closest_hit_time) {
+            if let Some(hit) = object.hit(ray, *ray_t.start()..=closest_hit_time) {
                 closest_hit_time = hit.t;
                 closest_hit = Some(hit);
             }
         }
         closest_hit
     }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.iter().fold(Aabb::empty(), |acc, object| {
+            acc.union(&object.bounding_box())
+        })
+    }
+
+    /// every object is picked equally often
+    fn pdf_value(&self, origin: Point3<T>, direction: Vec3<T>) -> T {
+        let sum = self.iter().fold(T::zero(), |sum, object| {
+            sum + object.pdf_value(origin, direction)
+        });
+        sum / (self.len().max(1) as f32).into()
+    }
+
+    /// the list must not be empty
+    fn random(&self, origin: Point3<T>, rng: &mut Rng) -> Vec3<T> {
+        self[rng.gen_range(0..self.len())].random(origin, rng)
+    }
 }
@@ -0,0 +1,757 @@
+use crate::color::Color;
+use crate::velem::VElem;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Write};
+use std::ops;
+use std::path::Path;
+
+/// Bits per channel of an encoded low dynamic range image
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitDepth {
+    Eight,
+    Sixteen,
+}
+
+/// In-memory framebuffer holding the linear radiance of every pixel.
+/// Pixels are stored row by row starting from the top left corner.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image<T: VElem> {
+    width: u64,
+    height: u64,
+    pixels: Vec<Color<T>>,
+}
+
+impl<T: VElem> Image<T> {
+    /// creates an all black image
+    pub fn new(width: u64, height: u64) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::default(); (width * height) as usize],
+        }
+    }
+
+    /// wraps already rendered pixels, panics if their count does not match the size
+    pub fn from_pixels(width: u64, height: u64, pixels: Vec<Color<T>>) -> Self {
+        assert_eq!(pixels.len() as u64, width * height);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[Color<T>] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [Color<T>] {
+        &mut self.pixels
+    }
+
+    /// iterates over the rows from top to bottom
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[Color<T>]> {
+        self.pixels.chunks(self.width.max(1) as usize)
+    }
+
+    /// encodes the image as an ASCII PPM (P3)
+    pub fn write_ppm<W>(&self, out: &mut W) -> Result<(), std::io::Error>
+    where
+        W: Write,
+    {
+        write!(out, "P3\n{} {}\n255\n", self.width, self.height)?;
+        for color in &self.pixels {
+            color.write_color(out)?;
+        }
+        Ok(())
+    }
+
+    /// encodes the image as an sRGB tagged PNG with 8 or 16 bits per channel
+    pub fn write_png<W>(&self, out: &mut W, depth: BitDepth) -> Result<(), std::io::Error>
+    where
+        W: Write,
+    {
+        let width = u32::try_from(self.width).map_err(std::io::Error::other)?;
+        let height = u32::try_from(self.height).map_err(std::io::Error::other)?;
+        let mut encoder = png::Encoder::new(out, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let data: Vec<u8> = match depth {
+            BitDepth::Eight => {
+                encoder.set_depth(png::BitDepth::Eight);
+                self.pixels.iter().flat_map(|c| c.to_rgb8()).collect()
+            }
+            BitDepth::Sixteen => {
+                encoder.set_depth(png::BitDepth::Sixteen);
+                // PNG stores 16 bit samples big endian
+                self.pixels
+                    .iter()
+                    .flat_map(|c| c.to_rgb16())
+                    .flat_map(u16::to_be_bytes)
+                    .collect()
+            }
+        };
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// encodes the unclamped linear radiance as a little endian Portable Float Map
+    pub fn write_pfm<W>(&self, out: &mut W) -> Result<(), std::io::Error>
+    where
+        W: Write,
+    {
+        // a negative scale marks the data as little endian
+        write!(out, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+        // PFM scanlines go from the bottom of the image to the top
+        for row in self.rows().rev() {
+            for color in row {
+                for c in [color.x(), color.y(), color.z()] {
+                    out.write_all(&c.to_f32().unwrap_or(0.0).to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// encodes the linear radiance as an uncompressed Radiance RGBE (.hdr) image
+    pub fn write_hdr<W>(&self, out: &mut W) -> Result<(), std::io::Error>
+    where
+        W: Write,
+    {
+        write!(
+            out,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )?;
+        for color in &self.pixels {
+            out.write_all(&to_rgbe(*color))?;
+        }
+        Ok(())
+    }
+
+    /// loads an image picking the decoder from the file extension
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        let path = path.as_ref();
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let mut input = BufReader::new(File::open(path)?);
+        match ext.as_deref() {
+            Some("pfm") => Self::read_pfm(&mut input),
+            Some("hdr") => Self::read_hdr(&mut input),
+            Some("png") => Self::read_png(&mut input),
+            Some("ppm") => Self::read_ppm(&mut input),
+            _ => Err(invalid_data(format!(
+                "unsupported image format '{}'",
+                path.display()
+            ))),
+        }
+    }
+
+    /// decodes a color (PF) or grayscale (Pf) Portable Float Map in either byte order
+    pub fn read_pfm<R>(input: &mut R) -> Result<Self, std::io::Error>
+    where
+        R: BufRead,
+    {
+        let channels = match read_header_line(input)?.as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            magic => return Err(invalid_data(format!("bad PFM magic '{}'", magic))),
+        };
+        let size = read_header_line(input)?;
+        let (width, height) = size
+            .split_once(char::is_whitespace)
+            .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+            .ok_or_else(|| invalid_data(format!("bad PFM size '{}'", size)))?;
+        let scale: f32 = read_header_line(input)?
+            .parse()
+            .map_err(|_| invalid_data("bad PFM scale"))?;
+        let from_bytes = if scale < 0.0 {
+            f32::from_le_bytes
+        } else {
+            f32::from_be_bytes
+        };
+
+        let bytes = data_size(width, height, channels as u64 * 4, "PFM")?;
+        let data = read_data(input, bytes, "PFM")?;
+        let texels: Vec<Color<T>> = data
+            .chunks(channels * 4)
+            .map(|texel| {
+                let c: Vec<T> = texel
+                    .chunks(4)
+                    .map(|b| from_bytes([b[0], b[1], b[2], b[3]]).into())
+                    .collect();
+                if channels == 3 {
+                    Color::new(c[0], c[1], c[2])
+                } else {
+                    Color::from([c[0]; 3])
+                }
+            })
+            .collect();
+        // bottom row first
+        let pixels = texels
+            .chunks(width.max(1) as usize)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// decodes an 8 or 16 bit PNG, the sRGB encoded samples are converted to linear radiance
+    /// and any alpha channel is dropped
+    pub fn read_png<R>(input: &mut R) -> Result<Self, std::io::Error>
+    where
+        R: Read,
+    {
+        let mut decoder = png::Decoder::new(input);
+        // palettes and packed gray values are widened to 8 bits
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().map_err(png_error)?;
+        let mut data = vec![0u8; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).map_err(png_error)?;
+        let channels = info.color_type.samples();
+        let samples: Vec<f32> = match info.bit_depth {
+            png::BitDepth::Sixteen => data[..info.buffer_size()]
+                .chunks(2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]) as f32 / 65535.0)
+                .collect(),
+            _ => data[..info.buffer_size()]
+                .iter()
+                .map(|b| *b as f32 / 255.0)
+                .collect(),
+        };
+        let pixels = samples
+            .chunks(channels)
+            .map(|texel| match texel.len() {
+                // gray with or without alpha
+                1 | 2 => srgb_color([texel[0]; 3]),
+                _ => srgb_color([texel[0], texel[1], texel[2]]),
+            })
+            .collect();
+        Ok(Self::from_pixels(
+            info.width as u64,
+            info.height as u64,
+            pixels,
+        ))
+    }
+
+    /// decodes an ASCII (P3) or binary (P6) PPM with sRGB encoded samples
+    pub fn read_ppm<R>(input: &mut R) -> Result<Self, std::io::Error>
+    where
+        R: BufRead,
+    {
+        let binary = match read_token(input)?.as_str() {
+            "P3" => false,
+            "P6" => true,
+            magic => return Err(invalid_data(format!("bad PPM magic '{}'", magic))),
+        };
+        let mut header = [0u64; 3];
+        for value in &mut header {
+            let token = read_token(input)?;
+            *value = token
+                .parse()
+                .map_err(|_| invalid_data(format!("bad PPM header value '{}'", token)))?;
+        }
+        let [width, height, max] = header;
+        if max == 0 || max > 65535 {
+            return Err(invalid_data(format!("bad PPM maximum value {}", max)));
+        }
+        // samples above 255 take two big endian bytes
+        let size = if max > 255 { 2 } else { 1 };
+        let bytes = data_size(width, height, 3 * size, "PPM")?;
+        let samples: Vec<u64> = if binary {
+            read_data(input, bytes, "PPM")?
+                .chunks(size as usize)
+                .map(|b| b.iter().fold(0, |acc, byte| acc << 8 | *byte as u64))
+                .collect()
+        } else {
+            (0..bytes / size)
+                .map(|_| {
+                    let token = read_token(input)?;
+                    token
+                        .parse()
+                        .map_err(|_| invalid_data(format!("bad PPM sample '{}'", token)))
+                })
+                .collect::<Result<_, _>>()?
+        };
+        let pixels = samples
+            .chunks(3)
+            .map(|rgb| srgb_color([rgb[0], rgb[1], rgb[2]].map(|c| c.min(max) as f32 / max as f32)))
+            .collect();
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// decodes a Radiance RGBE (.hdr) image stored flat or with the scanline run length encoding,
+    /// only the standard `-Y height +X width` orientation is supported
+    pub fn read_hdr<R>(input: &mut R) -> Result<Self, std::io::Error>
+    where
+        R: BufRead,
+    {
+        if !read_header_line(input)?.starts_with("#?") {
+            return Err(invalid_data("missing Radiance header"));
+        }
+        loop {
+            let line = read_header_line(input)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid_data(format!("unsupported format '{}'", format)));
+                }
+            }
+        }
+        let resolution = read_header_line(input)?;
+        let (width, height) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", h, "+X", w] => w.parse().ok().zip(h.parse().ok()),
+            _ => None,
+        }
+        .ok_or_else(|| invalid_data(format!("unsupported resolution '{}'", resolution)))?;
+
+        // RGBE takes four bytes per pixel before any compression
+        data_size(width, height, 4, "HDR")?;
+        // the pixels grow with the scanlines actually read, an empty image reads none
+        let rows = if width == 0 { 0 } else { height };
+        let mut pixels = Vec::new();
+        for _ in 0..rows {
+            let scanline = read_rgbe_scanline(input, width as usize)?;
+            pixels.extend(scanline.into_iter().map(from_rgbe));
+        }
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+}
+
+/// Bytes of pixel data in a `width` x `height` image as given by a file header, an error if
+/// that does not fit into 64 bits
+fn data_size(width: u64, height: u64, bytes_per_pixel: u64, format: &str) -> Result<u64, Error> {
+    width
+        .checked_mul(height)
+        .and_then(|n| n.checked_mul(bytes_per_pixel))
+        .ok_or_else(|| invalid_data(format!("bad {} size {}x{}", format, width, height)))
+}
+
+/// Reads `bytes` of pixel data. The buffer grows with the data actually there, so a huge size
+/// from a broken header fails at the end of the input instead of allocating it up front.
+fn read_data<R: Read>(input: &mut R, bytes: u64, format: &str) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    input.take(bytes).read_to_end(&mut data)?;
+    if (data.len() as u64) < bytes {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("{} ends before all pixels were read", format),
+        ));
+    }
+    Ok(data)
+}
+
+fn invalid_data(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+fn png_error(e: png::DecodingError) -> Error {
+    match e {
+        png::DecodingError::IoError(e) => e,
+        e => invalid_data(e.to_string()),
+    }
+}
+
+fn srgb_color<T: VElem>(encoded: [f32; 3]) -> Color<T> {
+    let [r, g, b] = encoded.map(|c| Color::srgb_to_linear(Into::<T>::into(c)));
+    Color::new(r, g, b)
+}
+
+/// Reads one whitespace separated token of a PNM header, skipping `#` comments.
+/// Exactly one whitespace byte after the token is consumed, as binary data starts right after it.
+fn read_token<R: BufRead>(input: &mut R) -> Result<String, std::io::Error> {
+    let mut token = Vec::new();
+    let mut comment = false;
+    loop {
+        let mut byte = [0u8; 1];
+        if input.read(&mut byte)? == 0 {
+            if token.is_empty() {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "truncated header"));
+            }
+            break;
+        }
+        match byte[0] {
+            b'\n' if comment => comment = false,
+            _ if comment => {}
+            b'#' if token.is_empty() => comment = true,
+            b if b.is_ascii_whitespace() => {
+                if !token.is_empty() {
+                    break;
+                }
+            }
+            b => token.push(b),
+        }
+    }
+    Ok(String::from_utf8_lossy(&token).into_owned())
+}
+
+/// reads one text line of a header, without the line break
+fn read_header_line<R: BufRead>(input: &mut R) -> Result<String, std::io::Error> {
+    let mut line = Vec::new();
+    if input.read_until(b'\n', &mut line)? == 0 {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "truncated header"));
+    }
+    Ok(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+fn read_rgbe_scanline<R: Read>(input: &mut R, width: usize) -> Result<Vec<[u8; 4]>, Error> {
+    if width == 0 {
+        return Ok(Vec::new());
+    }
+    let mut first = [0u8; 4];
+    input.read_exact(&mut first)?;
+    let encoded_width = ((first[2] as usize) << 8) | first[3] as usize;
+    let is_rle = (8..0x8000).contains(&width) && first[..2] == [2, 2] && encoded_width == width;
+    if !is_rle {
+        // flat scanline, the first pixel is already read and the rest are taken as they come
+        let data = read_data(input, (width as u64 - 1) * 4, "HDR")?;
+        let mut scanline = vec![first];
+        scanline.extend(data.chunks(4).map(|b| [b[0], b[1], b[2], b[3]]));
+        return Ok(scanline);
+    }
+    // run length encoded scanlines are less than 0x8000 pixels wide
+    let mut scanline = vec![[0u8; 4]; width];
+    // every channel is run length encoded separately
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 1];
+            input.read_exact(&mut count)?;
+            let (run, count) = if count[0] > 128 {
+                (true, (count[0] - 128) as usize)
+            } else {
+                (false, count[0] as usize)
+            };
+            if count == 0 || x + count > width {
+                return Err(invalid_data("bad RLE scanline"));
+            }
+            if run {
+                let mut value = [0u8; 1];
+                input.read_exact(&mut value)?;
+                for pixel in &mut scanline[x..x + count] {
+                    pixel[channel] = value[0];
+                }
+            } else {
+                let mut values = vec![0u8; count];
+                input.read_exact(&mut values)?;
+                for (pixel, value) in scanline[x..x + count].iter_mut().zip(values) {
+                    pixel[channel] = value;
+                }
+            }
+            x += count;
+        }
+    }
+    Ok(scanline)
+}
+
+fn from_rgbe<T: VElem>(rgbe: [u8; 4]) -> Color<T> {
+    if rgbe[3] == 0 {
+        return Color::default();
+    }
+    let f = 2f32.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        ((rgbe[0] as f32 + 0.5) * f).into(),
+        ((rgbe[1] as f32 + 0.5) * f).into(),
+        ((rgbe[2] as f32 + 0.5) * f).into(),
+    )
+}
+
+/// packs a color into RGBE, three 8 bit mantissas sharing the exponent of the largest channel
+fn to_rgbe<T: VElem>(color: Color<T>) -> [u8; 4] {
+    let [r, g, b] = [color.x(), color.y(), color.z()].map(|c| c.to_f32().unwrap_or(0.0).max(0.0));
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e with m in [0.5, 1)
+    let e = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f32.powi(e);
+    [
+        (r * scale) as u8,
+        (g * scale) as u8,
+        (b * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+impl<T: VElem> ops::Index<(u64, u64)> for Image<T> {
+    type Output = Color<T>;
+
+    /// pixel at (x, y), this WILL panic!() when out of bounds
+    fn index(&self, (x, y): (u64, u64)) -> &Self::Output {
+        assert!(x < self.width && y < self.height);
+        &self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+impl<T: VElem> ops::IndexMut<(u64, u64)> for Image<T> {
+    fn index_mut(&mut self, (x, y): (u64, u64)) -> &mut Self::Output {
+        assert!(x < self.width && y < self.height);
+        &mut self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn index_test() {
+        let mut img = Image::<f32>::new(3, 2);
+        img[(2, 1)] = Color::new(1.0, 0.5, 0.25);
+        assert_eq!(img[(2, 1)], Color::new(1.0, 0.5, 0.25));
+        assert_eq!(img.pixels()[5], Color::new(1.0, 0.5, 0.25));
+        assert_eq!(img[(0, 0)], Color::default());
+        assert_eq!(img.rows().count(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_index() {
+        let img = Image::<f32>::new(3, 2);
+        let _ = img[(3, 0)];
+    }
+
+    #[test]
+    fn write_ppm_test() {
+        let img = Image::from_pixels(
+            2,
+            1,
+            vec![Color::new(0.0, 0.0, 0.0), Color::new(0.25, 1.0, 4.0)],
+        );
+        let mut s = Vec::new();
+        img.write_ppm(&mut s).expect("Should write normally");
+        let st = String::from_utf8(s).unwrap();
+        assert_eq!(st, "P3\n2 1\n255\n0 0 0\n137 255 255\n");
+    }
+
+    #[test]
+    fn write_pfm_test() {
+        let img = Image::from_pixels(
+            1,
+            2,
+            vec![Color::new(0.5, 2.0, 100.0), Color::new(-1.0, 0.0, 0.25)],
+        );
+        let mut s = Vec::new();
+        img.write_pfm(&mut s).expect("Should write normally");
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&s[..header.len()], header);
+        let data: Vec<f32> = s[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        // bottom row first
+        assert_eq!(data, vec![-1.0, 0.0, 0.25, 0.5, 2.0, 100.0]);
+    }
+
+    #[test]
+    fn write_hdr_test() {
+        let img = Image::from_pixels(
+            2,
+            1,
+            vec![Color::new(0.0, 0.0, 0.0), Color::new(1.0, 0.5, 4.0)],
+        );
+        let mut s = Vec::new();
+        img.write_hdr(&mut s).expect("Should write normally");
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        assert_eq!(&s[..header.len()], header);
+        // 4.0 = 0.5 * 2^3 -> mantissa 128 with exponent 3 + 128
+        assert_eq!(&s[header.len()..], &[0, 0, 0, 0, 32, 16, 128, 131]);
+    }
+
+    #[test]
+    fn read_pfm_test() {
+        let img = Image::from_pixels(
+            2,
+            2,
+            vec![
+                Color::new(0.5, 2.0, 100.0),
+                Color::new(-1.0, 0.0, 0.25),
+                Color::new(3.0, 4.0, 5.0),
+                Color::new(6.0, 7.0, 8.0),
+            ],
+        );
+        let mut s = Vec::new();
+        img.write_pfm(&mut s).expect("Should write normally");
+        let read = Image::<f32>::read_pfm(&mut s.as_slice()).expect("Should read normally");
+        assert_eq!(read, img);
+
+        // big endian grayscale
+        let mut s = b"Pf\n1 1\n1.0\n".to_vec();
+        s.extend(0.75f32.to_be_bytes());
+        let read = Image::<f32>::read_pfm(&mut s.as_slice()).expect("Should read normally");
+        assert_eq!(read[(0, 0)], Color::new(0.75, 0.75, 0.75));
+
+        assert!(Image::<f32>::read_pfm(&mut b"P6\n1 1\n1.0\n".as_slice()).is_err());
+        assert!(Image::<f32>::read_pfm(&mut b"PF\n1 1\n-1.0\n\0\0".as_slice()).is_err());
+        // sizes from a hostile header neither overflow nor get allocated up front
+        let huge = b"PF\n4294967296 4294967296\n-1.0\n\0\0";
+        let err = Image::<f32>::read_pfm(&mut huge.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let large = b"PF\n100000 100000\n-1.0\n\0\0";
+        let err = Image::<f32>::read_pfm(&mut large.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_hdr_test() {
+        let img = Image::<f32>::from_pixels(
+            3,
+            1,
+            vec![
+                Color::new(0.0, 0.0, 0.0),
+                Color::new(1.0, 0.5, 4.0),
+                Color::new(1000.0, 0.01, 0.2),
+            ],
+        );
+        let mut s = Vec::new();
+        img.write_hdr(&mut s).expect("Should write normally");
+        let read = Image::<f32>::read_hdr(&mut s.as_slice()).expect("Should read normally");
+        assert_eq!((read.width(), read.height()), (3, 1));
+        for (a, b) in read.pixels().iter().zip(img.pixels()) {
+            let max = b.x().max(b.y()).max(b.z());
+            // RGBE keeps 8 bits of precision relative to the brightest channel
+            assert!((*a - *b).length() <= max / 64.0, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn read_hdr_rle_test() {
+        let mut s = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        s.extend([2, 2, 0, 8]);
+        // red: run of 8 x 128
+        s.extend([128 + 8, 128]);
+        // green: 8 literal values
+        s.extend([8, 0, 16, 32, 48, 64, 80, 96, 112]);
+        // blue: two runs
+        s.extend([128 + 4, 0, 128 + 4, 255]);
+        // exponent: run of 8 x 129 -> values scaled by 2 / 256
+        s.extend([128 + 8, 129]);
+        let read = Image::<f32>::read_hdr(&mut s.as_slice()).expect("Should read normally");
+        assert_eq!(read.width(), 8);
+        let scale = 2.0 / 256.0;
+        assert_eq!(read[(0, 0)], Color::new(128.5, 0.5, 0.5) * scale);
+        assert_eq!(read[(7, 0)], Color::new(128.5, 112.5, 255.5) * scale);
+
+        // run overflowing the scanline
+        let mut s = b"#?RADIANCE\n\n-Y 1 +X 8\n".to_vec();
+        s.extend([2, 2, 0, 8, 128 + 9, 1]);
+        assert!(Image::<f32>::read_hdr(&mut s.as_slice()).is_err());
+        // flipped orientation
+        let s = b"#?RADIANCE\n\n+Y 1 +X 8\n".to_vec();
+        assert!(Image::<f32>::read_hdr(&mut s.as_slice()).is_err());
+        // sizes from a hostile header neither overflow nor get allocated up front
+        let s = b"#?RADIANCE\n\n-Y 4294967296 +X 4294967296\n".to_vec();
+        let err = Image::<f32>::read_hdr(&mut s.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let mut s = b"#?RADIANCE\n\n-Y 100000 +X 100000\n".to_vec();
+        s.extend([1, 2, 3, 128]);
+        let err = Image::<f32>::read_hdr(&mut s.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    fn decode_png(data: &[u8]) -> (png::OutputInfo, Vec<u8>) {
+        let mut reader = png::Decoder::new(data).read_info().unwrap();
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap();
+        buf.truncate(info.buffer_size());
+        (info, buf)
+    }
+
+    #[test]
+    fn write_png_test() {
+        let img = Image::from_pixels(
+            2,
+            1,
+            vec![Color::new(0.0, 0.0, 0.0), Color::new(0.25, 1.0, 4.0)],
+        );
+        let mut s = Vec::new();
+        img.write_png(&mut s, BitDepth::Eight)
+            .expect("Should write normally");
+        let (info, data) = decode_png(&s);
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.bit_depth, png::BitDepth::Eight);
+        assert_eq!(data, vec![0, 0, 0, 137, 255, 255]);
+
+        let mut s = Vec::new();
+        img.write_png(&mut s, BitDepth::Sixteen)
+            .expect("Should write normally");
+        let (info, data) = decode_png(&s);
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        assert_eq!(data, vec![0, 0, 0, 0, 0, 0, 137, 127, 255, 255, 255, 255]);
+    }
+
+    fn close(a: Color<f32>, b: Color<f32>) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    #[test]
+    fn read_png_test() {
+        let img = Image::from_pixels(
+            2,
+            1,
+            vec![Color::new(0.0, 0.5, 1.0), Color::new(0.25, 0.1, 0.0)],
+        );
+        for depth in [BitDepth::Eight, BitDepth::Sixteen] {
+            let mut s = Vec::new();
+            img.write_png(&mut s, depth).unwrap();
+            let back = Image::<f32>::read_png(&mut s.as_slice()).unwrap();
+            assert_eq!((back.width(), back.height()), (2, 1));
+            for (a, b) in back.pixels().iter().zip(img.pixels()) {
+                assert!((*a - *b).length() < 0.01, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn read_ppm_test() {
+        let ascii = b"P3\n# a comment\n2 1\n255\n255 0 0\n0 0 255\n";
+        let img = Image::<f32>::read_ppm(&mut ascii.as_slice()).unwrap();
+        assert_eq!((img.width(), img.height()), (2, 1));
+        assert!(close(img[(0, 0)], Color::new(1.0, 0.0, 0.0)));
+        assert!(close(img[(1, 0)], Color::new(0.0, 0.0, 1.0)));
+
+        let mut binary = b"P6 1 1 255\n".to_vec();
+        binary.extend([10, 128, 255]);
+        let img = Image::<f32>::read_ppm(&mut binary.as_slice()).unwrap();
+        // 128 is mid gray in sRGB, about 0.216 in linear
+        assert!(close(img[(0, 0)], Color::new(0.003035, 0.215861, 1.0)));
+
+        // what write_ppm produces reads back
+        let orig = Image::from_pixels(1, 1, vec![Color::new(0.5, 0.25, 0.0)]);
+        let mut s = Vec::new();
+        orig.write_ppm(&mut s).unwrap();
+        let back = Image::<f32>::read_ppm(&mut s.as_slice()).unwrap();
+        assert!((back[(0, 0)] - orig[(0, 0)]).length() < 0.01);
+
+        assert!(Image::<f32>::read_ppm(&mut b"P5 1 1 255\n\0".as_slice()).is_err());
+        assert!(Image::<f32>::read_ppm(&mut b"P6 2 2 255\n\0\0\0".as_slice()).is_err());
+        // sizes from a hostile header neither overflow nor get allocated up front
+        let huge = b"P6 4294967296 4294967296 255\n\0\0\0";
+        let err = Image::<f32>::read_ppm(&mut huge.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        let large = b"P6 65536 65536 65535\n\0\0\0";
+        let err = Image::<f32>::read_ppm(&mut large.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::transform::Transform;
+use crate::velem::VElem;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Places a shared object in the world with a [`Transform`] from object to world space,
+/// so one mesh can show up many times without copying its geometry.
+pub struct Instance<T: VElem> {
+    object: Arc<dyn Hittable<T>>,
+    transform: Transform<T>,
+    bbox: Aabb<T>,
+}
+
+impl<T: VElem> Instance<T> {
+    pub fn new(object: Arc<dyn Hittable<T>>, transform: Transform<T>) -> Self {
+        let bbox = transform.bounding_box(&object.bounding_box());
+        Self {
+            object,
+            transform,
+            bbox,
+        }
+    }
+}
+
+impl<T: VElem> Hittable<T> for Instance<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        // The direction is not normalized after the transform, so the ray parameter t
+        // is the same in both spaces.
+        let to_object = self.transform.inverse();
+        let object_ray = Ray::with_time(
+            to_object.point(ray.origin()),
+            to_object.vector(ray.direction()),
+            ray.time(),
+        );
+        let mut hit = self.object.hit(&object_ray, ray_t)?;
+        hit.p = self.transform.point(hit.p);
+        hit.normal = self.transform.normal(hit.normal).unit_vector();
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Sphere = crate::sphere::Sphere<f32>;
+    type Transform = crate::transform::Transform<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use super::Instance;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+    use std::sync::Arc;
+
+    fn unit_sphere() -> Arc<Sphere> {
+        Arc::new(Sphere::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            1.0,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        ))
+    }
+
+    #[test]
+    fn translated_instance() {
+        let sphere = unit_sphere();
+        let moved = Instance::new(
+            sphere.clone(),
+            Transform::translate(Vec3::new(3.0, 0.0, -5.0)),
+        );
+        let ray = Ray::new(Vec3::new(3.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = moved
+            .hit(&ray, 0.0..=10.0)
+            .expect("Ray should hit the instance");
+        assert!((hit.t - 4.0).abs() < 1e-6);
+        assert!((hit.p - Vec3::new(3.0, 0.0, -4.0)).length() < 1e-6);
+        assert!((hit.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-6);
+        assert!(hit.front_facing);
+        // the shared sphere itself stays where it was
+        assert!(sphere.hit(&ray, 0.0..=10.0).is_none());
+        let e = moved.bounding_box().centroid();
+        assert_eq!(e, Vec3::new(3.0, 0.0, -5.0));
+    }
+
+    #[test]
+    fn scaled_instance() {
+        // ellipsoid stretched to a half axis of 2 along z
+        let stretched = Instance::new(
+            unit_sphere(),
+            Transform::scale(Vec3::new(1.0, 1.0, 2.0))
+                .then(&Transform::translate(Vec3::new(0.0, 0.0, -5.0))),
+        );
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = stretched.hit(&ray, 0.0..=10.0).unwrap();
+        assert!((hit.t - 3.0).abs() < 1e-5);
+
+        // the normal on the side of the ellipsoid leans towards z
+        let ray = Ray::new(Vec3::new(2.0, 0.0, -4.0), Vec3::new(-1.0, 0.0, 0.0));
+        let hit = stretched.hit(&ray, 0.0..=10.0).unwrap();
+        let expected = Vec3::new(0.75f32.sqrt(), 0.0, 0.25).unit_vector();
+        assert!((hit.normal - expected).length() < 1e-5, "{:?}", hit.normal);
+        assert!((hit.normal.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rotated_instance_misses() {
+        let rotated = Instance::new(
+            unit_sphere(),
+            Transform::translate(Vec3::new(0.0, 0.0, -5.0))
+                .then(&Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0)),
+        );
+        // rotating around the world origin swings the sphere over to -X
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(rotated.hit(&ray, 0.0..=10.0).is_none());
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(rotated.hit(&ray, 0.0..=10.0).is_some());
+    }
+}
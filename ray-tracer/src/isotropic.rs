@@ -0,0 +1,47 @@
+use crate::color::Color;
+use crate::material::{Material, ScatterRecord, Scattered};
+use crate::pdf::SpherePdf;
+use crate::texture::{SolidColor, Texture};
+use crate::velem::VElem;
+use std::sync::Arc;
+
+/// Phase function of a participating medium, scattering uniformly in all directions
+pub struct Isotropic<T: VElem> {
+    albedo: Arc<dyn Texture<T>>,
+}
+
+impl<T: VElem> Isotropic<T> {
+    pub fn new(albedo: Color<T>) -> Self {
+        Self::textured(SolidColor::new(albedo))
+    }
+
+    pub fn textured(albedo: impl Texture<T> + 'static) -> Self {
+        Self {
+            albedo: Arc::new(albedo),
+        }
+    }
+}
+
+impl<T: VElem> Material<T> for Isotropic<T> {
+    fn scatter(
+        &self,
+        _ray_in: &crate::ray::Ray<T>,
+        hit: &crate::hittable::HitRecord<T>,
+        _rng: &mut crate::rng::Rng,
+    ) -> Option<ScatterRecord<T>> {
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(hit.u, hit.v, hit.p),
+            scattered: Scattered::Pdf(Box::new(SpherePdf)),
+        })
+    }
+
+    /// uniform over the whole sphere of directions
+    fn scattering_pdf(
+        &self,
+        _ray_in: &crate::ray::Ray<T>,
+        _hit: &crate::hittable::HitRecord<T>,
+        _scattered: &crate::ray::Ray<T>,
+    ) -> T {
+        T::one() / (Into::<T>::into(4.0) * T::PI())
+    }
+}
@@ -1,17 +1,24 @@
 use crate::color::Color;
-use crate::material::Material;
-use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::material::{Material, ScatterRecord, Scattered};
+use crate::pdf::CosinePdf;
+use crate::texture::{SolidColor, Texture};
 use crate::velem::VElem;
-use num_traits::Zero;
+use std::sync::Arc;
 
 pub struct Lambertian<T: VElem> {
-    albedo: Color<T>,
+    albedo: Arc<dyn Texture<T>>,
 }
 
 impl<T: VElem> Lambertian<T> {
     pub fn new(albedo: Color<T>) -> Self {
-        Self { albedo }
+        Self::textured(SolidColor::new(albedo))
+    }
+
+    /// diffuse surface whose albedo is looked up in `albedo` at every hit
+    pub fn textured(albedo: impl Texture<T> + 'static) -> Self {
+        Self {
+            albedo: Arc::new(albedo),
+        }
     }
 }
 
@@ -20,14 +27,22 @@ impl<T: VElem> Material<T> for Lambertian<T> {
         &self,
         _ray_in: &crate::ray::Ray<T>,
         hit: &crate::hittable::HitRecord<T>,
-    ) -> Option<(crate::ray::Ray<T>, crate::color::Color<T>)> {
-        let scatter_dir = hit.normal + Vec3::random_unit_vec();
-        // hopefully catch degen scatter dirs
-        let scattered = if scatter_dir.is_zero() {
-            Ray::new(hit.p, hit.normal)
-        } else {
-            Ray::new(hit.p, scatter_dir)
-        };
-        Some((scattered, self.albedo))
+        _rng: &mut crate::rng::Rng,
+    ) -> Option<ScatterRecord<T>> {
+        Some(ScatterRecord {
+            attenuation: self.albedo.value(hit.u, hit.v, hit.p),
+            scattered: Scattered::Pdf(Box::new(CosinePdf::new(hit.normal))),
+        })
+    }
+
+    /// ideal diffuse reflection, cos(theta) / pi over the hemisphere of the normal
+    fn scattering_pdf(
+        &self,
+        _ray_in: &crate::ray::Ray<T>,
+        hit: &crate::hittable::HitRecord<T>,
+        scattered: &crate::ray::Ray<T>,
+    ) -> T {
+        let cos_theta = hit.normal.dot(&scattered.direction().unit_vector());
+        T::max(T::zero(), cos_theta / T::PI())
     }
 }
@@ -0,0 +1,36 @@
+pub mod aabb;
+pub mod background;
+pub mod bvh;
+pub mod camera;
+pub mod color;
+pub mod constant_medium;
+pub mod cuboid;
+pub mod dielectric;
+pub mod diffuse_light;
+pub mod environment;
+pub mod hittable;
+pub mod hittable_list;
+pub mod image;
+pub mod instance;
+pub mod isotropic;
+pub mod lambertian;
+pub mod material;
+pub mod mesh;
+pub mod metal;
+pub mod obj;
+pub mod onb;
+pub mod output;
+pub mod pdf;
+pub mod perlin;
+pub mod ply;
+pub mod quad;
+pub mod ray;
+pub mod rng;
+pub mod scene;
+pub mod sphere;
+pub mod texture;
+pub mod tonemap;
+pub mod transform;
+pub mod triangle;
+pub mod vec3;
+pub mod velem;
@@ -1,22 +1,162 @@
-use std::rc::Rc;
-mod camera;
-mod color;
-mod dielectric;
-mod hittable;
-mod hittable_list;
-mod lambertian;
-mod material;
-mod metal;
-mod ray;
-mod sphere;
-mod vec3;
-mod velem;
+use ray_tracer::background::Background;
+use ray_tracer::environment::EnvironmentMap;
+use ray_tracer::output::OutputFormat;
+use ray_tracer::scene::Scene;
+use ray_tracer::tonemap::{Operator, ToneMap};
+use ray_tracer::{
+    bvh, camera, color, dielectric, hittable_list, lambertian, metal, quad, sphere, vec3,
+};
+use std::fs::File;
+use std::io::{stderr, stdout, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
 
 type Point = vec3::Point3<f32>;
+type Vec3 = vec3::Vec3<f32>;
 type Camera = camera::Camera<f32>;
 type Color = color::Color<f32>;
 
-fn main() {
+const USAGE: &str = "\
+Usage: ray-tracer [OPTIONS] [OUTPUT]
+
+Renders a scene to OUTPUT, or as PPM to stdout when no output path is given.
+
+Options:
+  -s, --scene FILE        TOML scene description, the built-in scene is used without one
+  -o, --output FILE       output path, same as the positional OUTPUT
+  -f, --format FORMAT     ppm, png, png16, pfm or hdr, guessed from the output extension
+  -w, --width PIXELS      image width
+  -H, --height PIXELS     image height
+  -a, --aspect W:H        aspect ratio, each side 1 to 255
+      --spp N             samples per pixel
+      --max-depth N       maximum number of bounces per path
+  -j, --threads N         render threads, defaults to the number of CPUs
+      --seed N            seed for the random sampling
+      --tonemap OPERATOR  clamp, reinhard or aces, for the low dynamic range formats
+      --exposure EV       exposure adjustment in stops before tone mapping
+      --environment FILE  equirectangular .hdr or .pfm image lighting the scene
+  -h, --help              print this help
+
+Width, height and aspect override the scene file; giving the height and the aspect
+without a width derives the width from them.
+";
+
+struct Args {
+    output: Option<PathBuf>,
+    format: OutputFormat,
+    tone_map: ToneMap<f32>,
+    environment: Option<PathBuf>,
+    scene: Option<PathBuf>,
+    width: Option<u64>,
+    height: Option<u64>,
+    aspect: Option<(u8, u8)>,
+    samples_per_pixel: Option<u16>,
+    max_depth: Option<u16>,
+    threads: Option<usize>,
+    seed: Option<u64>,
+}
+
+/// parses the value following `flag`, at least `min` for numbers
+fn value<V>(args: &mut impl Iterator<Item = String>, flag: &str, min: V) -> Result<V, String>
+where
+    V: std::str::FromStr + PartialOrd + std::fmt::Display,
+{
+    let value = args
+        .next()
+        .ok_or_else(|| format!("{} needs a value", flag))?;
+    match value.parse() {
+        Ok(v) if v >= min => Ok(v),
+        Ok(_) => Err(format!("{} must be at least {}, got {}", flag, min, value)),
+        Err(_) => Err(format!("invalid value '{}' for {}", value, flag)),
+    }
+}
+
+fn parse_aspect(value: &str) -> Result<(u8, u8), String> {
+    let invalid = || format!("invalid aspect ratio '{}', expected W:H like 16:9", value);
+    let (w, h) = value.split_once(':').ok_or_else(invalid)?;
+    match (w.parse::<u8>(), h.parse::<u8>()) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(invalid()),
+    }
+}
+
+/// `Ok(None)` when the help was asked for
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut output = None;
+    let mut format = None;
+    let mut operator = Operator::Clamp;
+    let mut exposure = 0.0;
+    let mut environment = None;
+    let mut scene = None;
+    let mut width = None;
+    let mut height = None;
+    let mut aspect = None;
+    let mut samples_per_pixel = None;
+    let mut max_depth = None;
+    let mut threads = None;
+    let mut seed = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut path = |flag: &str| -> Result<PathBuf, String> {
+            args.next()
+                .map(PathBuf::from)
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-s" | "--scene" => scene = Some(path(&arg)?),
+            "-o" | "--output" => output = Some(path(&arg)?),
+            "--environment" => environment = Some(path(&arg)?),
+            "-f" | "--format" => {
+                let value = args.next().ok_or("--format needs a value")?;
+                format = Some(value.parse()?);
+            }
+            "--tonemap" => {
+                let value = args.next().ok_or("--tonemap needs a value")?;
+                operator = value.parse()?;
+            }
+            "--exposure" => exposure = value(&mut args, &arg, f32::MIN)?,
+            "-w" | "--width" => width = Some(value(&mut args, &arg, 1)?),
+            "-H" | "--height" => height = Some(value(&mut args, &arg, 2)?),
+            "-a" | "--aspect" => {
+                let value = args.next().ok_or("--aspect needs a value")?;
+                aspect = Some(parse_aspect(&value)?);
+            }
+            "--spp" => samples_per_pixel = Some(value(&mut args, &arg, 1)?),
+            "--max-depth" => max_depth = Some(value(&mut args, &arg, 1)?),
+            "-j" | "--threads" => threads = Some(value(&mut args, &arg, 1)?),
+            "--seed" => seed = Some(value(&mut args, &arg, 0)?),
+            _ if output.is_none() && !arg.starts_with('-') => output = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+    if width.is_some() && height.is_some() && aspect.is_some() {
+        return Err("give at most two of --width, --height and --aspect".to_string());
+    }
+    let format = match (format, &output) {
+        (Some(format), _) => format,
+        (None, Some(path)) => OutputFormat::from_path(path)
+            .ok_or_else(|| format!("cannot guess the format of '{}'", path.display()))?,
+        (None, None) => OutputFormat::Ppm,
+    };
+    Ok(Some(Args {
+        output,
+        format,
+        tone_map: ToneMap::new(operator).exposure(exposure),
+        environment,
+        scene,
+        width,
+        height,
+        aspect,
+        samples_per_pixel,
+        max_depth,
+        threads,
+        seed,
+    }))
+}
+
+/// the scene rendered when no scene file is given
+fn default_scene() -> Scene<f32> {
     let ground = lambertian::Lambertian::new(Color::new(0.8, 0.8, 0.0));
     let center = lambertian::Lambertian::new(Color::new(0.1, 0.2, 0.5));
     let left = dielectric::Dielectric::new(1.50);
@@ -24,36 +164,169 @@ fn main() {
     let right = metal::Metal::new(Color::new(0.8, 0.6, 0.2), 1.0);
 
     let world: hittable_list::HittableList<f32> = vec![
-        Rc::new(sphere::Sphere::new(
+        Arc::new(sphere::Sphere::new(
             Point::from([0.0, 0.0, -1.2]),
             0.5,
             center,
         )),
-        Rc::new(sphere::Sphere::new(
+        Arc::new(sphere::Sphere::new(
             Point::from([-1.0, 0.0, -1.0]),
             0.5,
             left,
         )),
-        Rc::new(sphere::Sphere::new(
+        Arc::new(sphere::Sphere::new(
             Point::from([-1.0, 0.0, -1.0]),
             0.4,
             bubble,
         )),
-        Rc::new(sphere::Sphere::new(
+        Arc::new(sphere::Sphere::new(
             Point::from([1.0, 0.0, -1.0]),
             0.5,
             right,
         )),
-        Rc::new(sphere::Sphere::new(
-            Point::from([0.0, -100.5, -1.0]),
-            100.0,
+        Arc::new(quad::Quad::new(
+            Point::from([-100.0, -0.5, -100.0]),
+            Vec3::new(0.0, 0.0, 200.0),
+            Vec3::new(200.0, 0.0, 0.0),
             ground,
         )),
     ];
 
-    // image
-    let aspect_ratio = (16, 9);
-    let width = 400;
-    let c = Camera::new(aspect_ratio, width, 100, 10);
-    c.render(&world)
+    let camera = Camera::builder()
+        .aspect_ratio((16, 9))
+        .image_width(400)
+        .samples_per_pixel(100)
+        .max_depth(10)
+        .vfov(90.0)
+        .look_from(Point::new(0.0, 0.0, 0.0))
+        .look_at(Point::new(0.0, 0.0, -1.0))
+        .vup(Vec3::new(0.0, 1.0, 0.0))
+        .defocus_angle(0.0)
+        .focus_dist(1.0)
+        .background(Background::sky());
+    Scene { camera, world }
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\nrun with --help for the usage", e);
+            std::process::exit(2);
+        }
+    };
+
+    let mut scene = match &args.scene {
+        Some(path) => Scene::load(path).unwrap_or_else(|e| {
+            eprintln!("error: {}: {}", path.display(), e);
+            std::process::exit(1);
+        }),
+        None => default_scene(),
+    };
+    if let Some(path) = &args.environment {
+        match EnvironmentMap::open(path) {
+            Ok(map) => {
+                scene.camera = scene
+                    .camera
+                    .background(Background::Environment(Arc::new(map)))
+            }
+            Err(e) => {
+                eprintln!("error: cannot load '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut camera = scene.camera;
+    if let Some(aspect) = args.aspect {
+        camera = camera.aspect_ratio(aspect);
+    }
+    match (args.width, args.height, args.aspect) {
+        (None, Some(height), Some((w, h))) => {
+            let width = height
+                .checked_mul(w as u64)
+                .and_then(|n| n.checked_add(h as u64 / 2))
+                .map(|n| n / h as u64)
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "error: a height of {} is too large for the aspect ratio {}:{}\n\
+                         run with --help for the usage",
+                        height, w, h
+                    );
+                    std::process::exit(2);
+                });
+            camera = camera.image_width(width.max(1)).image_height(height);
+        }
+        (width, height, _) => {
+            if let Some(width) = width {
+                camera = camera.image_width(width);
+            }
+            if let Some(height) = height {
+                camera = camera.image_height(height);
+            }
+        }
+    }
+    // a tiny width or a very wide aspect ratio can leave too few rows to render
+    let (width, height) = camera.image_size();
+    if height < 2 {
+        eprintln!(
+            "error: the image would be {}x{} pixels, it needs a height of at least 2\n\
+             run with --help for the usage",
+            width, height
+        );
+        std::process::exit(2);
+    }
+    if let Some(samples_per_pixel) = args.samples_per_pixel {
+        camera = camera.samples_per_pixel(samples_per_pixel);
+    }
+    if let Some(max_depth) = args.max_depth {
+        camera = camera.max_depth(max_depth);
+    }
+    if let Some(threads) = args.threads {
+        camera = camera.threads(threads);
+    }
+    if let Some(seed) = args.seed {
+        camera = camera.seed(seed);
+    }
+
+    // opened up front so a bad path fails before the render rather than after it
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("error: cannot create '{}': {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => Box::new(stdout().lock()),
+    };
+
+    let world = bvh::BvhNode::new(scene.world);
+    let c = camera.build();
+    let image = c.render_with_progress(&world, |done, total| {
+        let mut err = stderr().lock();
+        write!(err, "\rLines remaining: {}  ", total - done).unwrap();
+        let _ = err.flush();
+    });
+    write!(stderr().lock(), "\r Done                          \n").unwrap();
+
+    // HDR formats keep the radiance as rendered, everything else is tone mapped first
+    let image = if args.format.is_hdr() {
+        image
+    } else {
+        args.tone_map.apply(&image)
+    };
+    // flushed explicitly, dropping a BufWriter would swallow the error
+    let written = args
+        .format
+        .write(&image, &mut output)
+        .and_then(|()| output.flush());
+    if let Err(e) = written {
+        eprintln!("error: failed to write the image: {}", e);
+        std::process::exit(1);
+    }
 }
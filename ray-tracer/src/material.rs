@@ -1,8 +1,68 @@
 use crate::color::Color;
 use crate::hittable::HitRecord;
+use crate::pdf::Pdf;
 use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::vec3::Point3;
 use crate::velem::VElem;
+use num_traits::Zero;
+use std::sync::Arc;
 
-pub trait Material<T: VElem> {
-    fn scatter(&self, ray_in: &Ray<T>, hit: &HitRecord<T>) -> Option<(Ray<T>, Color<T>)>;
+/// How the light continues after a scattering event
+pub enum Scattered<T: VElem> {
+    /// Mirror like scattering into a single direction, which is followed as is.
+    /// No density exists for it, so it takes no part in importance sampling.
+    Specular(Ray<T>),
+    /// Scattering into many directions. The next direction is drawn from this density and the
+    /// sample is weighted by [`Material::scattering_pdf`] over it.
+    Pdf(Box<dyn Pdf<T>>),
+}
+
+pub struct ScatterRecord<T: VElem> {
+    /// Fraction of the light kept per color channel. For [`Scattered::Pdf`] the BRDF times the
+    /// cosine term is `attenuation * scattering_pdf`.
+    pub attenuation: Color<T>,
+    pub scattered: Scattered<T>,
+}
+
+pub trait Material<T: VElem>: Send + Sync {
+    /// how `ray_in` scatters at `hit`, `None` if it is absorbed,
+    /// all random choices are drawn from `rng`
+    fn scatter(
+        &self,
+        ray_in: &Ray<T>,
+        hit: &HitRecord<T>,
+        rng: &mut Rng,
+    ) -> Option<ScatterRecord<T>>;
+
+    /// Density with which the surface scatters `ray_in` into `scattered`, with respect to solid
+    /// angle. Only used for [`Scattered::Pdf`], so specular materials keep the default of zero.
+    fn scattering_pdf(&self, _ray_in: &Ray<T>, _hit: &HitRecord<T>, _scattered: &Ray<T>) -> T {
+        T::zero()
+    }
+
+    /// light given off by the surface at (u, v) / `p`, black for everything but light sources
+    fn emitted(&self, _u: T, _v: T, _p: Point3<T>) -> Color<T> {
+        Color::zero()
+    }
+}
+
+/// lets one material instance be shared between several objects
+impl<T: VElem> Material<T> for Arc<dyn Material<T>> {
+    fn scatter(
+        &self,
+        ray_in: &Ray<T>,
+        hit: &HitRecord<T>,
+        rng: &mut Rng,
+    ) -> Option<ScatterRecord<T>> {
+        self.as_ref().scatter(ray_in, hit, rng)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray<T>, hit: &HitRecord<T>, scattered: &Ray<T>) -> T {
+        self.as_ref().scattering_pdf(ray_in, hit, scattered)
+    }
+
+    fn emitted(&self, u: T, v: T, p: Point3<T>) -> Color<T> {
+        self.as_ref().emitted(u, v, p)
+    }
 }
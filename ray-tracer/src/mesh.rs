@@ -0,0 +1,350 @@
+use crate::aabb::Aabb;
+use crate::bvh::BvhNode;
+use crate::color::Color;
+use crate::hittable::{HitRecord, Hittable};
+use crate::hittable_list::HittableList;
+use crate::lambertian::Lambertian;
+use crate::material::{Material, ScatterRecord};
+use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use num_traits::Zero;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Indexed triangle buffers.
+/// `normals`, `uvs` and `colors` are either empty or hold one entry per position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData<T: VElem> {
+    pub positions: Vec<Point3<T>>,
+    pub normals: Vec<Vec3<T>>,
+    pub uvs: Vec<(T, T)>,
+    pub colors: Vec<Color<T>>,
+    /// three indices into the vertex buffers per triangle, counter-clockwise seen from the front
+    pub indices: Vec<[usize; 3]>,
+}
+
+impl<T: VElem> MeshData<T> {
+    pub fn new(positions: Vec<Point3<T>>, indices: Vec<[usize; 3]>) -> Self {
+        Self {
+            positions,
+            indices,
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the vertex normals with the area weighted average of the face normals around
+    /// each vertex, for smooth shading of meshes that come without normals.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::zero(); self.positions.len()];
+        for &[a, b, c] in &self.indices {
+            let [p0, p1, p2] = [self.positions[a], self.positions[b], self.positions[c]];
+            // the cross product is twice the area long
+            let n = (p1 - p0).cross(&(p2 - p0));
+            for i in [a, b, c] {
+                normals[i] += n;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| if n.is_zero() { n } else { n.unit_vector() })
+            .collect();
+    }
+
+    fn corners(&self, triangle: usize) -> [Point3<T>; 3] {
+        self.indices[triangle].map(|i| self.positions[i])
+    }
+}
+
+/// One face of a [`TriangleMesh`], referring to the shared buffers by index
+struct MeshTriangle<T: VElem> {
+    mesh: Arc<MeshData<T>>,
+    triangle: usize,
+    material: Arc<dyn Material<T>>,
+}
+
+impl<T: VElem> Hittable<T> for MeshTriangle<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        let corners = self.mesh.corners(self.triangle);
+        let (t, b1, b2) = crate::triangle::intersect(corners, ray, ray_t)?;
+        let b0 = T::one() - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.triangle];
+
+        let [p0, p1, p2] = corners;
+        let geometric_normal = (p1 - p0).cross(&(p2 - p0)).unit_vector();
+        let front_facing = ray.direction().dot(&geometric_normal) < T::zero();
+
+        // Interpolate the vertex normals for smooth shading, flipped to the side the ray
+        // came from so the shading normal never points into the surface.
+        let normal = if self.mesh.normals.is_empty() {
+            geometric_normal
+        } else {
+            let n = self.mesh.normals[i0] * b0
+                + self.mesh.normals[i1] * b1
+                + self.mesh.normals[i2] * b2;
+            if n.is_zero() {
+                geometric_normal
+            } else {
+                n.unit_vector()
+            }
+        };
+        let normal = if (normal.dot(&geometric_normal) < T::zero()) == front_facing {
+            -normal
+        } else {
+            normal
+        };
+
+        let (u, v) = if self.mesh.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let [uv0, uv1, uv2] = [self.mesh.uvs[i0], self.mesh.uvs[i1], self.mesh.uvs[i2]];
+            (
+                uv0.0 * b0 + uv1.0 * b1 + uv2.0 * b2,
+                uv0.1 * b0 + uv1.1 * b1 + uv2.1 * b2,
+            )
+        };
+
+        let color = (!self.mesh.colors.is_empty()).then(|| {
+            self.mesh.colors[i0] * b0 + self.mesh.colors[i1] * b1 + self.mesh.colors[i2] * b2
+        });
+
+        Some(HitRecord {
+            t,
+            u,
+            v,
+            color,
+            p: ray.at(t),
+            normal,
+            front_facing,
+            material: self.material.clone(),
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        crate::triangle::bounding_box(self.mesh.corners(self.triangle))
+    }
+}
+
+/// Triangles sharing one set of vertex buffers and one material.
+/// The faces are kept in their own [`BvhNode`], so the whole mesh is a single entry in the scene.
+pub struct TriangleMesh<T: VElem> {
+    mesh: Arc<MeshData<T>>,
+    bvh: BvhNode<T>,
+}
+
+impl<T: VElem> TriangleMesh<T> {
+    pub fn new(mesh: MeshData<T>, material: impl Material<T> + 'static) -> Self {
+        let material: Arc<dyn Material<T>> = Arc::new(material);
+        let n = mesh.positions.len();
+        assert!(
+            mesh.indices.iter().flatten().all(|&i| i < n),
+            "mesh index out of bounds"
+        );
+        assert!(mesh.normals.is_empty() || mesh.normals.len() == n);
+        assert!(mesh.uvs.is_empty() || mesh.uvs.len() == n);
+        assert!(mesh.colors.is_empty() || mesh.colors.len() == n);
+
+        let mesh = Arc::new(mesh);
+        let triangles: HittableList<T> = (0..mesh.indices.len())
+            .map(|triangle| {
+                Arc::new(MeshTriangle {
+                    mesh: mesh.clone(),
+                    triangle,
+                    material: material.clone(),
+                }) as Arc<dyn Hittable<T>>
+            })
+            .collect();
+        Self {
+            mesh,
+            bvh: BvhNode::new(triangles),
+        }
+    }
+
+    /// Mesh without a material of its own, diffuse with the vertex colors interpolated across
+    /// each face. Panics if the mesh has no vertex colors.
+    pub fn vertex_colored(mesh: MeshData<T>) -> Self {
+        assert!(!mesh.colors.is_empty(), "mesh without vertex colors");
+        Self::new(mesh, VertexColored::default())
+    }
+
+    pub fn data(&self) -> &MeshData<T> {
+        &self.mesh
+    }
+}
+
+impl<T: VElem> Hittable<T> for TriangleMesh<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        self.bvh.hit(ray, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        self.bvh.bounding_box()
+    }
+}
+
+/// [`Lambertian`] whose albedo is the vertex color of the hit, black where there is none
+struct VertexColored<T: VElem> {
+    white: Lambertian<T>,
+}
+
+impl<T: VElem> Default for VertexColored<T> {
+    fn default() -> Self {
+        Self {
+            white: Lambertian::new(Color::new(T::one(), T::one(), T::one())),
+        }
+    }
+}
+
+impl<T: VElem> Material<T> for VertexColored<T> {
+    fn scatter(
+        &self,
+        ray_in: &Ray<T>,
+        hit: &HitRecord<T>,
+        rng: &mut Rng,
+    ) -> Option<ScatterRecord<T>> {
+        let mut record = self.white.scatter(ray_in, hit, rng)?;
+        record.attenuation = hit.color.unwrap_or(Color::zero());
+        Some(record)
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray<T>, hit: &HitRecord<T>, scattered: &Ray<T>) -> T {
+        self.white.scattering_pdf(ray_in, hit, scattered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type MeshData = super::MeshData<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type TriangleMesh = super::TriangleMesh<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+
+    /// unit square on the z = -1 plane split along its diagonal
+    fn square() -> MeshData {
+        MeshData::new(
+            vec![
+                Vec3::new(0.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, -1.0),
+                Vec3::new(1.0, 1.0, -1.0),
+                Vec3::new(0.0, 1.0, -1.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+        )
+    }
+
+    fn gray() -> Lambertian<f32> {
+        Lambertian::new(Color::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn test_hits_both_triangles() {
+        let mesh = TriangleMesh::new(square(), gray());
+        for (x, y) in [(0.75, 0.25), (0.25, 0.75)] {
+            let ray = Ray::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, -1.0));
+            let hit = mesh.hit(&ray, 0.0..=10.0).expect("Ray should hit the mesh");
+            assert_eq!(hit.t, 1.0);
+            assert!(hit.front_facing);
+            assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+        }
+        let ray = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(mesh.hit(&ray, 0.0..=10.0).is_none());
+    }
+
+    #[test]
+    fn test_interpolated_uv() {
+        let mut data = square();
+        data.uvs = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let mesh = TriangleMesh::new(data, gray());
+        let ray = Ray::new(Vec3::new(0.25, 0.75, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = mesh.hit(&ray, 0.0..=10.0).unwrap();
+        assert!((hit.u - 0.25).abs() < 1e-6 && (hit.v - 0.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_smooth_normals() {
+        let mut data = square();
+        // tilt the normals outwards along x, like the side of a cylinder
+        data.normals = vec![
+            Vec3::new(-1.0, 0.0, 1.0).unit_vector(),
+            Vec3::new(1.0, 0.0, 1.0).unit_vector(),
+            Vec3::new(1.0, 0.0, 1.0).unit_vector(),
+            Vec3::new(-1.0, 0.0, 1.0).unit_vector(),
+        ];
+        let mesh = TriangleMesh::new(data, gray());
+        let down = Vec3::new(0.0, 0.0, -1.0);
+
+        let center = mesh
+            .hit(&Ray::new(Vec3::new(0.5, 0.25, 0.0), down), 0.0..=10.0)
+            .unwrap();
+        assert!((center.normal - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-6);
+
+        let right = mesh
+            .hit(&Ray::new(Vec3::new(0.9, 0.5, 0.0), down), 0.0..=10.0)
+            .unwrap();
+        assert!(right.normal.x() > 0.5);
+        assert!((right.normal.length() - 1.0).abs() < 1e-6);
+
+        // seen from behind the shading normal is flipped along with the face
+        let back = mesh
+            .hit(
+                &Ray::new(Vec3::new(0.9, 0.5, -2.0), Vec3::new(0.0, 0.0, 1.0)),
+                0.0..=10.0,
+            )
+            .unwrap();
+        assert!(!back.front_facing);
+        assert!(back.normal.z() < 0.0 && back.normal.x() < -0.5);
+    }
+
+    #[test]
+    fn test_compute_normals() {
+        // two faces folded along the y axis
+        let mut data = MeshData::new(
+            vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(-1.0, 0.0, -1.0),
+                Vec3::new(1.0, 0.0, -1.0),
+            ],
+            vec![[0, 1, 2], [0, 3, 1]],
+        );
+        data.compute_normals();
+        assert!((data.normals[0] - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-6);
+        assert!((data.normals[2] - Vec3::new(-1.0, 0.0, 1.0).unit_vector()).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_vertex_colored() {
+        let mut data = square();
+        // red along x = 0, green along x = 1
+        let red = Color::new(1.0, 0.0, 0.0);
+        let green = Color::new(0.0, 1.0, 0.0);
+        data.colors = vec![red, green, green, red];
+        let mesh = TriangleMesh::vertex_colored(data);
+        let mut rng = crate::rng::pixel_rng(0, 1, 0, 0);
+        let mut albedo = |x: f32, y: f32| {
+            let ray = Ray::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, -1.0));
+            let hit = mesh.hit(&ray, 0.0..=10.0).unwrap();
+            let scatter = hit.material.scatter(&ray, &hit, &mut rng).unwrap();
+            (scatter.attenuation, hit.material)
+        };
+        // the color blends smoothly across both faces, which share one material
+        let (left, first) = albedo(0.25, 0.75);
+        let (right, second) = albedo(0.75, 0.25);
+        assert!((left - Color::new(0.75, 0.25, 0.0)).length() < 1e-6);
+        assert!((right - Color::new(0.25, 0.75, 0.0)).length() < 1e-6);
+        assert!(std::sync::Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bad_index() {
+        TriangleMesh::new(
+            MeshData::new(vec![Vec3::new(0.0, 0.0, 0.0)], vec![[0, 1, 2]]),
+            gray(),
+        );
+    }
+}
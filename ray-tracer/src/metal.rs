@@ -1,18 +1,27 @@
 use crate::color::Color;
-use crate::material::Material;
+use crate::material::{Material, ScatterRecord, Scattered};
 use crate::ray::Ray;
+use crate::texture::{SolidColor, Texture};
 use crate::vec3::Vec3;
 use crate::velem::VElem;
+use std::sync::Arc;
 
 pub struct Metal<T: VElem> {
-    albedo: Color<T>,
+    albedo: Arc<dyn Texture<T>>,
     fuzz: T,
 }
 
 impl<T: VElem> Metal<T> {
     pub fn new(albedo: Color<T>, fuzz: T) -> Self {
-        let fuzz = T::min(fuzz, T::one());
-        Self { albedo, fuzz }
+        Self::textured(SolidColor::new(albedo), fuzz)
+    }
+
+    /// metal whose tint is looked up in `albedo` at every hit
+    pub fn textured(albedo: impl Texture<T> + 'static, fuzz: T) -> Self {
+        Self {
+            albedo: Arc::new(albedo),
+            fuzz: T::min(fuzz, T::one()),
+        }
     }
 }
 
@@ -21,15 +30,19 @@ impl<T: VElem> Material<T> for Metal<T> {
         &self,
         ray_in: &crate::ray::Ray<T>,
         hit: &crate::hittable::HitRecord<T>,
-    ) -> Option<(crate::ray::Ray<T>, crate::color::Color<T>)> {
+        rng: &mut crate::rng::Rng,
+    ) -> Option<ScatterRecord<T>> {
         let reflected = if self.fuzz == T::zero() {
             ray_in.direction().reflect(hit.normal)
         } else {
-            ray_in.direction().reflect(hit.normal) + Vec3::random_unit_vec() * self.fuzz
+            ray_in.direction().reflect(hit.normal) + Vec3::random_unit_vec(rng) * self.fuzz
         };
-        let scattered = Ray::new(hit.p, reflected);
+        let scattered = Ray::with_time(hit.p, reflected, ray_in.time());
         if scattered.direction().dot(&hit.normal) > T::zero() {
-            Some((scattered, self.albedo))
+            Some(ScatterRecord {
+                attenuation: self.albedo.value(hit.u, hit.v, hit.p),
+                scattered: Scattered::Specular(scattered),
+            })
         } else {
             None
         }
@@ -0,0 +1,581 @@
+use crate::color::Color;
+use crate::dielectric::Dielectric;
+use crate::hittable_list::HittableList;
+use crate::lambertian::Lambertian;
+use crate::material::Material;
+use crate::mesh::{MeshData, TriangleMesh};
+use crate::metal::Metal;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Materials of an MTL library by name
+pub type Materials<T> = HashMap<String, Arc<dyn Material<T>>>;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(std::io::Error),
+    /// malformed statement on `line`, counting from 1
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<std::io::Error> for ObjError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Loads a Wavefront OBJ file with the material libraries it references from `mtllib`,
+/// which are looked up relative to the OBJ file.
+/// Every material gets one [`TriangleMesh`] holding all faces drawn with it.
+pub fn load_obj<T: VElem>(path: impl AsRef<Path>) -> Result<HittableList<T>, ObjError> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let input = BufReader::new(File::open(path)?);
+    read_obj(input, |name| {
+        let file = File::open(dir.join(name))?;
+        read_mtl(BufReader::new(file))
+    })
+}
+
+/// Reads OBJ statements from `input`, `mtllib` is called with the name of every material
+/// library the file references.
+/// Faces with more than three corners are split into a triangle fan.
+/// Like other viewers only malformed geometry is an error: faces drawn with a material that
+/// is not defined, for instance because its library failed to load, get a light gray default.
+pub fn read_obj<T: VElem, R: BufRead>(
+    input: R,
+    mut mtllib: impl FnMut(&str) -> Result<Materials<T>, ObjError>,
+) -> Result<HittableList<T>, ObjError> {
+    let mut positions: Vec<Point3<T>> = Vec::new();
+    let mut normals: Vec<Vec3<T>> = Vec::new();
+    let mut uvs: Vec<(T, T)> = Vec::new();
+    let mut materials: Materials<T> = HashMap::new();
+    // faces grouped by material name in order of first use, "" for the default material
+    let mut groups: Vec<(String, Group<T>)> = Vec::new();
+    let mut current = String::new();
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let error = |message: String| ObjError::Parse {
+            line: number,
+            message,
+        };
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => positions.push(parse_vec3(tokens).map_err(error)?),
+            Some("vn") => normals.push(parse_vec3(tokens).map_err(error)?),
+            Some("vt") => {
+                // the optional third texture coordinate is ignored
+                let u = parse_number(tokens.next()).map_err(error)?;
+                let v = tokens
+                    .next()
+                    .map_or(Ok(T::zero()), |v| parse_number(Some(v)));
+                uvs.push((u, v.map_err(error)?));
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                if corners.len() < 3 {
+                    return Err(error("face with less than three corners".to_string()));
+                }
+                let group = match groups.iter_mut().position(|(name, _)| *name == current) {
+                    Some(i) => &mut groups[i].1,
+                    None => {
+                        groups.push((current.clone(), Group::default()));
+                        &mut groups.last_mut().unwrap().1
+                    }
+                };
+                let indices: Vec<usize> = corners
+                    .into_iter()
+                    .map(|corner| group.vertex(corner, &positions, &uvs, &normals))
+                    .collect();
+                for i in 1..indices.len() - 1 {
+                    group
+                        .data
+                        .indices
+                        .push([indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+            Some("usemtl") => {
+                // looked up once all libraries are read
+                current = tokens.collect::<Vec<_>>().join(" ");
+            }
+            Some("mtllib") => {
+                for name in tokens {
+                    // a missing or broken library leaves its materials undefined
+                    if let Ok(library) = mtllib(name) {
+                        materials.extend(library);
+                    }
+                }
+            }
+            // objects, groups, smoothing groups and everything else do not change the geometry
+            _ => {}
+        }
+    }
+
+    let default: Arc<dyn Material<T>> = Arc::new(Lambertian::new(Color::new(
+        0.8.into(),
+        0.8.into(),
+        0.8.into(),
+    )));
+    Ok(groups
+        .into_iter()
+        .map(|(name, group)| {
+            let material = materials.get(&name).unwrap_or(&default).clone();
+            Arc::new(TriangleMesh::new(group.finish(), material)) as _
+        })
+        .collect())
+}
+
+/// Reads the materials of an MTL library.
+/// Transparent materials (`d` < 1 or `illum` 4, 6, 7) become [`Dielectric`] with the index `Ni`,
+/// mirror like ones (`illum` 3 or 5, or a black `Kd` with a specular `Ks`) become [`Metal`] with
+/// its fuzz derived from the `Ns` exponent, everything else is [`Lambertian`] with `Kd`.
+pub fn read_mtl<T: VElem, R: BufRead>(input: R) -> Result<Materials<T>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlParams<T>)> = None;
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        let number = number + 1;
+        let error = |message: String| ObjError::Parse {
+            line: number,
+            message,
+        };
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        if keyword == "newmtl" {
+            if let Some((name, params)) = current.take() {
+                materials.insert(name, params.material());
+            }
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            current = Some((name, MtlParams::default()));
+            continue;
+        }
+        let params = match current.as_mut() {
+            Some((_, params)) => params,
+            None => return Err(error(format!("'{}' before the first newmtl", keyword))),
+        };
+        match keyword {
+            "Kd" => params.kd = parse_vec3(tokens).map_err(error)?,
+            "Ks" => params.ks = parse_vec3(tokens).map_err(error)?,
+            "Ns" => params.ns = parse_number(tokens.next()).map_err(error)?,
+            "Ni" => params.ni = Some(parse_number(tokens.next()).map_err(error)?),
+            "d" => params.dissolve = parse_number(tokens.next()).map_err(error)?,
+            "Tr" => params.dissolve = T::one() - parse_number(tokens.next()).map_err(error)?,
+            "illum" => {
+                let illum = tokens.next().unwrap_or_default();
+                params.illum = illum
+                    .parse()
+                    .map_err(|_| error(format!("invalid illumination model '{}'", illum)))?;
+            }
+            _ => {}
+        }
+    }
+    if let Some((name, params)) = current {
+        materials.insert(name, params.material());
+    }
+    Ok(materials)
+}
+
+/// Faces of one material with their own, de-duplicated vertex buffers
+struct Group<T: VElem> {
+    data: MeshData<T>,
+    /// (position, uv, normal) OBJ indices to the index in `data`
+    vertices: HashMap<Corner, usize>,
+    has_uvs: bool,
+    has_normals: bool,
+}
+
+impl<T: VElem> Default for Group<T> {
+    fn default() -> Self {
+        Self {
+            data: MeshData::default(),
+            vertices: HashMap::new(),
+            has_uvs: true,
+            has_normals: true,
+        }
+    }
+}
+
+impl<T: VElem> Group<T> {
+    fn vertex(
+        &mut self,
+        corner: Corner,
+        positions: &[Point3<T>],
+        uvs: &[(T, T)],
+        normals: &[Vec3<T>],
+    ) -> usize {
+        if let Some(&index) = self.vertices.get(&corner) {
+            return index;
+        }
+        let (p, uv, n) = corner;
+        self.has_uvs &= uv.is_some();
+        self.has_normals &= n.is_some();
+        self.data.positions.push(positions[p]);
+        self.data
+            .uvs
+            .push(uv.map_or((T::zero(), T::zero()), |uv| uvs[uv]));
+        self.data
+            .normals
+            .push(n.map_or(Vec3::zero(), |n| normals[n]));
+        let index = self.data.positions.len() - 1;
+        self.vertices.insert(corner, index);
+        index
+    }
+
+    /// drops the uvs and normals unless every corner of every face had one
+    fn finish(mut self) -> MeshData<T> {
+        if !self.has_uvs {
+            self.data.uvs.clear();
+        }
+        if !self.has_normals {
+            self.data.normals.clear();
+        }
+        self.data
+    }
+}
+
+/// zero based (position, uv, normal) indices of a face corner
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// parses `v`, `v/vt`, `v//vn` or `v/vt/vn`
+fn parse_corner(
+    corner: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<Corner, String> {
+    let mut parts = corner.split('/');
+    let p = parse_index(parts.next(), positions, corner)?
+        .ok_or_else(|| format!("face corner '{}' without a position", corner))?;
+    let uv = parse_index(parts.next(), uvs, corner)?;
+    let n = parse_index(parts.next(), normals, corner)?;
+    if parts.next().is_some() {
+        return Err(format!("invalid face corner '{}'", corner));
+    }
+    Ok((p, uv, n))
+}
+
+/// resolves a one based index, negative ones count back from the end of the `count` elements
+fn parse_index(index: Option<&str>, count: usize, corner: &str) -> Result<Option<usize>, String> {
+    let index = match index {
+        None | Some("") => return Ok(None),
+        Some(index) => index,
+    };
+    let i: isize = index
+        .parse()
+        .map_err(|_| format!("invalid index '{}' in face corner '{}'", index, corner))?;
+    let resolved = if i > 0 { i - 1 } else { count as isize + i };
+    if i == 0 || resolved < 0 || resolved >= count as isize {
+        return Err(format!(
+            "index {} out of range in face corner '{}'",
+            i, corner
+        ));
+    }
+    Ok(Some(resolved as usize))
+}
+
+fn parse_number<T: VElem>(token: Option<&str>) -> Result<T, String> {
+    let token = token.ok_or("missing number")?;
+    token
+        .parse::<f32>()
+        .map(Into::into)
+        .map_err(|_| format!("invalid number '{}'", token))
+}
+
+fn parse_vec3<'a, T: VElem>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Vec3<T>, String> {
+    Ok(Vec3::new(
+        parse_number(tokens.next())?,
+        parse_number(tokens.next())?,
+        parse_number(tokens.next())?,
+    ))
+}
+
+struct MtlParams<T: VElem> {
+    kd: Color<T>,
+    ks: Color<T>,
+    ns: T,
+    ni: Option<T>,
+    dissolve: T,
+    illum: u8,
+}
+
+impl<T: VElem> Default for MtlParams<T> {
+    fn default() -> Self {
+        Self {
+            kd: Color::new(0.8.into(), 0.8.into(), 0.8.into()),
+            ks: Color::zero(),
+            ns: T::zero(),
+            ni: None,
+            dissolve: T::one(),
+            illum: 2,
+        }
+    }
+}
+
+impl<T: VElem> MtlParams<T> {
+    fn material(&self) -> Arc<dyn Material<T>> {
+        let max = |c: Color<T>| c.x().max(c.y()).max(c.z());
+        if self.dissolve < T::one() || matches!(self.illum, 4 | 6 | 7) {
+            Arc::new(Dielectric::new(self.ni.unwrap_or(1.5.into())))
+        } else if matches!(self.illum, 3 | 5)
+            || (max(self.kd).is_zero() && max(self.ks) > T::zero())
+        {
+            // a Phong exponent of 0 is fully rough, large exponents approach a mirror
+            let fuzz = (Into::<T>::into(2.0) / (self.ns + 2.0.into())).sqrt();
+            Arc::new(Metal::new(self.ks, fuzz))
+        } else {
+            Arc::new(Lambertian::new(self.kd))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::material::{ScatterRecord, Scattered};
+    use crate::ray::Ray;
+
+    const SCENE_MTL: &str = "\
+# three materials
+newmtl red
+Kd 0.8 0.1 0.1
+
+newmtl glass
+illum 7
+Ni 1.33
+
+newmtl steel
+Kd 0 0 0
+Ks 0.9 0.8 0.7
+Ns 1000
+";
+
+    fn scatter(ray: &Ray<f32>, hit: &HitRecord<f32>) -> ScatterRecord<f32> {
+        let mut rng = crate::rng::pixel_rng(0, 1, 0, 0);
+        hit.material.scatter(ray, hit, &mut rng).unwrap()
+    }
+
+    /// hit on a surface at the origin facing +z, struck at 45 degrees
+    fn surface_hit(material: &Arc<dyn Material<f32>>) -> (Ray<f32>, HitRecord<f32>) {
+        let ray = Ray::new(Vec3::new(-1.0, 0.0, 1.0), Vec3::new(1.0, 0.0, -1.0));
+        let hit = HitRecord {
+            p: Vec3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 0.0, 1.0),
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            front_facing: true,
+            color: None,
+            material: material.clone(),
+        };
+        (ray, hit)
+    }
+
+    fn materials(name: &str) -> Result<Materials<f32>, ObjError> {
+        assert_eq!(name, "scene.mtl");
+        read_mtl(SCENE_MTL.as_bytes())
+    }
+
+    #[test]
+    fn quad_face_is_fan_triangulated() {
+        let obj = "\
+v 0 0 -1
+v 1 0 -1
+v 1 1 -1
+v 0 1 -1
+vn 0 0 1
+vt 0 0
+vt 1 1
+f 1/1/1 2/1/1 3/2/1 4/2/1
+";
+        let list = read_obj::<f32, _>(obj.as_bytes(), |_| unreachable!()).unwrap();
+        assert_eq!(list.len(), 1);
+        let ray = Ray::new(Vec3::new(0.2, 0.8, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = list.hit(&ray, 0.0..=10.0).expect("Ray should hit the face");
+        assert_eq!(hit.t, 1.0);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn faces_are_grouped_by_material() {
+        let obj = "\
+mtllib scene.mtl
+v 0 0 -1
+v 1 0 -1
+v 0 1 -1
+v 0 0 -2
+v 1 0 -2
+v 0 1 -2
+usemtl red
+f 1 2 3
+usemtl glass
+f -3 -2 -1
+usemtl red
+f 1//  2 3
+";
+        // `1//` is fine, both the uv and the normal index are optional
+        let list = read_obj::<f32, _>(obj.as_bytes(), materials).unwrap();
+        assert_eq!(list.len(), 2);
+
+        // the red faces sit at z = -1, the glass one behind them at z = -2
+        let ray = Ray::new(Vec3::new(0.2, 0.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = list.hit(&ray, 0.0..=10.0).unwrap();
+        assert_eq!(hit.t, 1.0);
+        let red = scatter(&ray, &hit);
+        assert_eq!(red.attenuation, Color::new(0.8, 0.1, 0.1));
+        assert!(matches!(red.scattered, Scattered::Pdf(_)));
+
+        let ray = Ray::new(Vec3::new(0.2, 0.2, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = list.hit(&ray, 0.0..=10.0).unwrap();
+        assert_eq!(hit.t, 1.0);
+        let glass = scatter(&ray, &hit);
+        assert_eq!(glass.attenuation, Color::new(1.0, 1.0, 1.0));
+        assert!(matches!(glass.scattered, Scattered::Specular(_)));
+    }
+
+    #[test]
+    fn unknown_materials_fall_back_to_default() {
+        let obj = "\
+mtllib scene.mtl
+v 0 0 -1
+v 1 0 -1
+v 0 1 -1
+usemtl nope
+f 1 2 3
+";
+        let list = read_obj::<f32, _>(obj.as_bytes(), materials).unwrap();
+        let ray = Ray::new(Vec3::new(0.2, 0.2, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = list.hit(&ray, 0.0..=10.0).unwrap();
+        let gray = scatter(&ray, &hit);
+        assert_eq!(gray.attenuation, Color::new(0.8, 0.8, 0.8));
+        assert!(matches!(gray.scattered, Scattered::Pdf(_)));
+    }
+
+    #[test]
+    fn mtl_materials() {
+        let materials = read_mtl::<f32, _>(SCENE_MTL.as_bytes()).unwrap();
+        assert_eq!(materials.len(), 3);
+
+        // Kd only is diffuse
+        let (ray, hit) = surface_hit(&materials["red"]);
+        let red = scatter(&ray, &hit);
+        assert_eq!(red.attenuation, Color::new(0.8, 0.1, 0.1));
+        assert!(matches!(red.scattered, Scattered::Pdf(_)));
+
+        // black Kd with Ks is a metal tinted by Ks, a large Ns keeps it close to a mirror
+        let (ray, hit) = surface_hit(&materials["steel"]);
+        let steel = scatter(&ray, &hit);
+        assert_eq!(steel.attenuation, Color::new(0.9, 0.8, 0.7));
+        let Scattered::Specular(reflected) = steel.scattered else {
+            panic!("steel should be specular");
+        };
+        let mirror = Vec3::new(1.0, 0.0, 1.0).unit_vector();
+        assert!((reflected.direction().unit_vector() - mirror).length() < 0.1);
+
+        // illum 7 is glass refracting with Ni, sin(45 deg) / 1.33 inside
+        let (ray, hit) = surface_hit(&materials["glass"]);
+        let mut rng = crate::rng::pixel_rng(0, 1, 0, 0);
+        let refracted = (0..100)
+            .find_map(
+                |_| match hit.material.scatter(&ray, &hit, &mut rng)?.scattered {
+                    Scattered::Specular(r) if r.direction().z() < 0.0 => Some(r.direction()),
+                    _ => None,
+                },
+            )
+            .expect("glass should refract most of the time");
+        let sin_t = refracted.unit_vector().x();
+        assert!(
+            (sin_t - std::f32::consts::FRAC_1_SQRT_2 / 1.33).abs() < 1e-4,
+            "{}",
+            sin_t
+        );
+
+        assert!(read_mtl::<f32, _>("Kd 1 1 1\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn parse_errors_have_line_numbers() {
+        let err = read_obj::<f32, _>("v 0 0 0\nv 1 x 0\n".as_bytes(), |_| unreachable!())
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "line 2: invalid number 'x'");
+
+        let err = read_obj::<f32, _>("v 0 0 0\nf 1 2 3\n".as_bytes(), |_| unreachable!())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "line 2: index 2 out of range in face corner '2'"
+        );
+
+        let err = read_obj::<f32, _>("mtllib scene.mtl\nf 1 2\n".as_bytes(), materials)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ObjError::Parse { line: 2, .. }));
+    }
+
+    #[test]
+    fn load_obj_resolves_mtllib() {
+        let dir = std::env::temp_dir().join(format!("ray-tracer-obj-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("scene.mtl"), SCENE_MTL).unwrap();
+        std::fs::write(
+            dir.join("scene.obj"),
+            "mtllib scene.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl glass\nf 1 2 3\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("missing.obj"),
+            "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl glass\nf 1 2 3\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken.mtl"), "Kd 1 1 1\n").unwrap();
+        std::fs::write(
+            dir.join("broken.obj"),
+            "mtllib broken.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let list = load_obj::<f32>(dir.join("scene.obj")).unwrap();
+        assert_eq!(list.len(), 1);
+        // libraries that cannot be read or parsed leave the default material
+        for name in ["missing.obj", "broken.obj"] {
+            let list = load_obj::<f32>(dir.join(name)).unwrap();
+            let ray = Ray::new(Vec3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0));
+            let hit = list.hit(&ray, 0.0..=10.0).unwrap();
+            assert_eq!(scatter(&ray, &hit).attenuation, Color::new(0.8, 0.8, 0.8));
+        }
+        assert!(load_obj::<f32>(dir.join("nothing.obj")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,81 @@
+use crate::vec3::Vec3;
+use crate::velem::VElem;
+
+/// Orthonormal basis around a direction `w`, used to turn directions sampled around the
+/// local z axis into world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Onb<T: VElem> {
+    u: Vec3<T>,
+    v: Vec3<T>,
+    w: Vec3<T>,
+}
+
+impl<T: VElem> Onb<T> {
+    /// basis whose `w` axis points along `n`, which does not have to be normalized
+    pub fn new(n: Vec3<T>) -> Self {
+        let w = n.unit_vector();
+        // any helper vector works as long as it is not parallel to w
+        let helper = if w.x().abs() > 0.9.into() {
+            Vec3::new(T::zero(), T::one(), T::zero())
+        } else {
+            Vec3::new(T::one(), T::zero(), T::zero())
+        };
+        let v = w.cross(&helper).unit_vector();
+        let u = v.cross(&w);
+        Self { u, v, w }
+    }
+
+    #[inline]
+    pub fn u(&self) -> Vec3<T> {
+        self.u
+    }
+
+    #[inline]
+    pub fn v(&self) -> Vec3<T> {
+        self.v
+    }
+
+    #[inline]
+    pub fn w(&self) -> Vec3<T> {
+        self.w
+    }
+
+    /// maps `local` given in (u, v, w) coordinates to world space
+    pub fn transform(&self, local: Vec3<T>) -> Vec3<T> {
+        self.u * local.x() + self.v * local.y() + self.w * local.z()
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn orthonormal_test() {
+        for n in [
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.3, 0.8, 0.1),
+        ] {
+            let b = Onb::<f32>::new(n);
+            for axis in [b.u(), b.v(), b.w()] {
+                assert!((axis.length() - 1.0).abs() < 1e-6);
+            }
+            assert!(b.u().dot(&b.v()).abs() < 1e-6);
+            assert!(b.v().dot(&b.w()).abs() < 1e-6);
+            assert!(b.w().dot(&b.u()).abs() < 1e-6);
+            // right handed
+            assert!((b.u().cross(&b.v()) - b.w()).length() < 1e-6);
+            assert!((b.w() - n.unit_vector()).length() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn transform_test() {
+        let b = Onb::<f32>::new(Vec3::new(0.0, 1.0, 0.0));
+        assert!((b.transform(Vec3::new(0.0, 0.0, 1.0)) - Vec3::new(0.0, 1.0, 0.0)).length() < 1e-6);
+        let v = Vec3::new(0.3, -0.2, 0.5);
+        assert!((b.transform(v).length() - v.length()).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::image::{BitDepth, Image};
+use crate::velem::VElem;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Encodings the renderer can write images in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// ASCII PPM (P3)
+    Ppm,
+    /// 8 bit sRGB PNG
+    Png,
+    /// 16 bit sRGB PNG
+    Png16,
+    /// Portable Float Map holding the unclamped linear radiance
+    Pfm,
+    /// Radiance RGBE holding the unclamped linear radiance
+    Hdr,
+}
+
+impl OutputFormat {
+    /// guesses the format from the file extension, `.png` defaults to 8 bits
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "ppm" => Some(Self::Ppm),
+            "png" => Some(Self::Png),
+            "pfm" => Some(Self::Pfm),
+            "hdr" => Some(Self::Hdr),
+            _ => None,
+        }
+    }
+
+    /// formats storing the unclamped radiance, these skip tone mapping
+    pub fn is_hdr(self) -> bool {
+        matches!(self, Self::Pfm | Self::Hdr)
+    }
+
+    pub fn write<T, W>(self, image: &Image<T>, out: &mut W) -> Result<(), std::io::Error>
+    where
+        T: VElem,
+        W: Write,
+    {
+        match self {
+            Self::Ppm => image.write_ppm(out),
+            Self::Png => image.write_png(out, BitDepth::Eight),
+            Self::Png16 => image.write_png(out, BitDepth::Sixteen),
+            Self::Pfm => image.write_pfm(out),
+            Self::Hdr => image.write_hdr(out),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ppm" => Ok(Self::Ppm),
+            "png" | "png8" => Ok(Self::Png),
+            "png16" => Ok(Self::Png16),
+            "pfm" => Ok(Self::Pfm),
+            "hdr" => Ok(Self::Hdr),
+            _ => Err(format!("unknown output format '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn from_path_test() {
+        assert_eq!(OutputFormat::from_path("out.png"), Some(OutputFormat::Png));
+        assert_eq!(
+            OutputFormat::from_path("a/b/OUT.PPM"),
+            Some(OutputFormat::Ppm)
+        );
+        assert_eq!(OutputFormat::from_path("out.pfm"), Some(OutputFormat::Pfm));
+        assert_eq!(OutputFormat::from_path("out.hdr"), Some(OutputFormat::Hdr));
+        assert_eq!(OutputFormat::from_path("out.jpg"), None);
+        assert_eq!(OutputFormat::from_path("out"), None);
+    }
+
+    #[test]
+    fn from_str_test() {
+        assert_eq!("png16".parse(), Ok(OutputFormat::Png16));
+        assert_eq!("PNG".parse(), Ok(OutputFormat::Png));
+        assert_eq!("ppm".parse(), Ok(OutputFormat::Ppm));
+        assert!("tiff".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn is_hdr_test() {
+        assert!(OutputFormat::Pfm.is_hdr());
+        assert!(OutputFormat::Hdr.is_hdr());
+        assert!(!OutputFormat::Png16.is_hdr());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::hittable::Hittable;
+use crate::onb::Onb;
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use rand::Rng as _;
+
+/// Probability density over directions, with respect to solid angle.
+/// `generate` draws directions distributed according to `value`.
+pub trait Pdf<T: VElem> {
+    fn value(&self, direction: Vec3<T>) -> T;
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T>;
+}
+
+/// every direction equally likely
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpherePdf;
+
+impl<T: VElem> Pdf<T> for SpherePdf {
+    fn value(&self, _direction: Vec3<T>) -> T {
+        T::one() / (Into::<T>::into(4.0) * T::PI())
+    }
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T> {
+        Vec3::random_unit_vec(rng)
+    }
+}
+
+/// directions over the hemisphere around a normal, weighted by the cosine to it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CosinePdf<T: VElem> {
+    uvw: Onb<T>,
+}
+
+impl<T: VElem> CosinePdf<T> {
+    pub fn new(normal: Vec3<T>) -> Self {
+        Self {
+            uvw: Onb::new(normal),
+        }
+    }
+}
+
+impl<T: VElem> Pdf<T> for CosinePdf<T> {
+    fn value(&self, direction: Vec3<T>) -> T {
+        let cos_theta = direction.unit_vector().dot(&self.uvw.w());
+        T::max(T::zero(), cos_theta / T::PI())
+    }
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T> {
+        self.uvw.transform(Vec3::random_cosine_direction(rng))
+    }
+}
+
+/// directions from `origin` towards `objects`, used to aim at the light sources
+pub struct HittablePdf<'a, T: VElem> {
+    objects: &'a dyn Hittable<T>,
+    origin: Point3<T>,
+}
+
+impl<'a, T: VElem> HittablePdf<'a, T> {
+    pub fn new(objects: &'a dyn Hittable<T>, origin: Point3<T>) -> Self {
+        Self { objects, origin }
+    }
+}
+
+impl<T: VElem> Pdf<T> for HittablePdf<'_, T> {
+    fn value(&self, direction: Vec3<T>) -> T {
+        self.objects.pdf_value(self.origin, direction)
+    }
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T> {
+        self.objects.random(self.origin, rng)
+    }
+}
+
+/// Even blend of two densities, e.g. sampling the light sources half of the time
+pub struct MixturePdf<'a, T: VElem> {
+    pdfs: [&'a dyn Pdf<T>; 2],
+}
+
+impl<'a, T: VElem> MixturePdf<'a, T> {
+    pub fn new(first: &'a dyn Pdf<T>, second: &'a dyn Pdf<T>) -> Self {
+        Self {
+            pdfs: [first, second],
+        }
+    }
+}
+
+impl<T: VElem> Pdf<T> for MixturePdf<'_, T> {
+    fn value(&self, direction: Vec3<T>) -> T {
+        let half: T = 0.5.into();
+        half * self.pdfs[0].value(direction) + half * self.pdfs[1].value(direction)
+    }
+
+    fn generate(&self, rng: &mut Rng) -> Vec3<T> {
+        if rng.gen_bool(0.5) {
+            self.pdfs[0].generate(rng)
+        } else {
+            self.pdfs[1].generate(rng)
+        }
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+    use rand::SeedableRng;
+
+    /// Monte Carlo estimate of the integral of `pdf` over the sphere, should be 1
+    fn integral(pdf: &dyn Pdf<f32>) -> f32 {
+        let mut rng = Rng::seed_from_u64(1);
+        let n = 20000;
+        let sum: f32 = (0..n)
+            .map(|_| pdf.value(Vec3::random_unit_vec(&mut rng)))
+            .sum();
+        sum * 4.0 * std::f32::consts::PI / n as f32
+    }
+
+    #[test]
+    fn sphere_pdf_test() {
+        assert!((integral(&SpherePdf) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cosine_pdf_test() {
+        let normal = Vec3::new(0.0, 1.0, 1.0);
+        let pdf = CosinePdf::new(normal);
+        assert!((integral(&pdf) - 1.0).abs() < 0.03);
+        assert_eq!(pdf.value(-normal), 0.0);
+        let mut rng = Rng::seed_from_u64(2);
+        let n = 10000;
+        let mut mean_cos = 0.0;
+        for _ in 0..n {
+            let d = pdf.generate(&mut rng);
+            assert!((d.length() - 1.0).abs() < 1e-4);
+            let cos = d.dot(&normal.unit_vector());
+            assert!(cos >= -1e-6);
+            mean_cos += cos / n as f32;
+        }
+        // E[cos] under a cosine density is 2/3
+        assert!((mean_cos - 2.0 / 3.0).abs() < 0.02, "{}", mean_cos);
+    }
+
+    #[test]
+    fn hittable_pdf_test() {
+        use crate::hittable_list::HittableList;
+        use crate::lambertian::Lambertian;
+        use crate::quad::Quad;
+        use crate::sphere::Sphere;
+        use std::sync::Arc;
+
+        let gray = || Lambertian::new(crate::color::Color::new(0.5, 0.5, 0.5));
+        let lights: HittableList<f32> = vec![
+            Arc::new(Quad::new(
+                Vec3::new(-1.0, 1.0, -1.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 2.0),
+                gray(),
+            )),
+            Arc::new(Sphere::new(Vec3::new(0.0, -2.0, 0.0), 1.0, gray())),
+        ];
+        for origin in [Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, -2.5, 0.0)] {
+            let pdf = HittablePdf::new(&lights, origin);
+            assert!((integral(&pdf) - 1.0).abs() < 0.05, "{:?}", origin);
+            let mut rng = Rng::seed_from_u64(3);
+            for _ in 0..100 {
+                let d = pdf.generate(&mut rng);
+                assert!(pdf.value(d) > 0.0);
+                assert!(lights
+                    .hit(&crate::ray::Ray::new(origin, d), 0.001..=100.0)
+                    .is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn mixture_pdf_test() {
+        let cosine = CosinePdf::new(Vec3::new(0.0, 0.0, 1.0));
+        let mixture = MixturePdf::new(&SpherePdf, &cosine);
+        assert!((integral(&mixture) - 1.0).abs() < 0.03);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        assert_eq!(mixture.value(down), 0.5 / (4.0 * std::f32::consts::PI));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise on an integer lattice.
+/// Every lattice point gets a random unit gradient, picked by hashing its coordinates through
+/// three permutation tables. The tables come from `seed`, so a noise field is reproducible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Perlin<T: VElem> {
+    gradients: Vec<Vec3<T>>,
+    perm: [[usize; POINT_COUNT]; 3],
+}
+
+impl<T: VElem> Perlin<T> {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| Vec3::random_unit_vec(&mut rng))
+            .collect();
+        let perm = std::array::from_fn(|_| {
+            let mut p = std::array::from_fn(|i| i);
+            p.shuffle(&mut rng);
+            p
+        });
+        Self { gradients, perm }
+    }
+
+    /// Noise at `p` in about [-1, 1], zero on every lattice point.
+    /// The gradients are interpolated trilinearly with Hermite smoothing of the weights,
+    /// which keeps the field free of visible grid lines.
+    pub fn noise(&self, p: Point3<T>) -> T {
+        let cell = [p.x(), p.y(), p.z()].map(|c| c.floor());
+        let frac = [p.x() - cell[0], p.y() - cell[1], p.z() - cell[2]];
+        let cell = cell.map(|c| c.to_i64().unwrap_or(0));
+        let smooth = frac.map(|t| t * t * (Into::<T>::into(3.0) - Into::<T>::into(2.0) * t));
+
+        let mut sum = T::zero();
+        for corner in 0..8 {
+            let offset = [0, 1, 2].map(|axis| (corner >> axis) & 1);
+            let index = (0..3).fold(0, |hash, axis| {
+                let c = (cell[axis] + offset[axis] as i64) & (POINT_COUNT as i64 - 1);
+                hash ^ self.perm[axis][c as usize]
+            });
+            let mut weight = T::one();
+            let mut towards = [T::zero(); 3];
+            for axis in 0..3 {
+                if offset[axis] == 1 {
+                    weight = weight * smooth[axis];
+                    towards[axis] = frac[axis] - T::one();
+                } else {
+                    weight = weight * (T::one() - smooth[axis]);
+                    towards[axis] = frac[axis];
+                }
+            }
+            sum = sum + weight * self.gradients[index].dot(&Vec3::from(towards));
+        }
+        sum
+    }
+
+    /// Absolute value of the sum of `depth` octaves of signed noise, each twice the frequency
+    /// and half the weight. Octaves of opposite sign cancel, which gives the veins of marble.
+    pub fn turbulence(&self, p: Point3<T>, depth: usize) -> T {
+        let mut sum = T::zero();
+        let mut p = p;
+        let mut weight = T::one();
+        for _ in 0..depth {
+            sum = sum + weight * self.noise(p);
+            weight = weight * 0.5.into();
+            p *= Into::<T>::into(2.0);
+        }
+        sum.abs()
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn noise_test() {
+        let perlin = Perlin::<f32>::new(1);
+        // zero on the lattice
+        assert_eq!(perlin.noise(Point3::new(3.0, -2.0, 7.0)), 0.0);
+        let mut any_nonzero = false;
+        for i in 0..1000 {
+            let p = Point3::new(i as f32 * 0.137, i as f32 * -0.071, i as f32 * 0.029);
+            let n = perlin.noise(p);
+            assert!((-1.5..=1.5).contains(&n), "noise {} out of range", n);
+            any_nonzero |= n != 0.0;
+        }
+        assert!(any_nonzero);
+        // the lattice wraps around every 256 units
+        let p = Point3::new(0.3, 0.6, 0.9);
+        let wrapped = p + Vec3::new(256.0, 0.0, 256.0);
+        assert!((perlin.noise(p) - perlin.noise(wrapped)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let perlin = Perlin::<f32>::new(2);
+        let p = Point3::new(1.999, 0.5, 0.5);
+        let q = Point3::new(2.001, 0.5, 0.5);
+        assert!((perlin.noise(p) - perlin.noise(q)).abs() < 1e-2);
+    }
+
+    #[test]
+    fn seed_test() {
+        let p = Point3::new(0.5, 0.25, 0.75);
+        let a = Perlin::<f32>::new(3);
+        assert_eq!(a.noise(p), Perlin::new(3).noise(p));
+        assert_ne!(a.noise(p), Perlin::new(4).noise(p));
+        assert!(a.turbulence(p, 7) >= 0.0);
+    }
+
+    #[test]
+    fn turbulence_sums_signed_octaves() {
+        let perlin = Perlin::<f32>::new(5);
+        let octaves = |p: Point3<f32>| -> Vec<f32> {
+            (0..3)
+                .map(|i| perlin.noise(p * 2f32.powi(i)) / 2f32.powi(i))
+                .collect()
+        };
+        let mut cancelled = false;
+        for i in 0..64 {
+            let p = Point3::new(
+                0.37 * i as f32,
+                0.11 * i as f32 + 0.3,
+                0.23 * i as f32 + 0.7,
+            );
+            let signed: f32 = octaves(p).iter().sum();
+            assert!((perlin.turbulence(p, 3) - signed.abs()).abs() < 1e-6);
+            // the absolute value is taken once at the end, not per octave
+            let absolute: f32 = octaves(p).iter().map(|n| n.abs()).sum();
+            cancelled |= signed.abs() < absolute - 1e-3;
+        }
+        assert!(cancelled);
+    }
+}
@@ -0,0 +1,561 @@
+use crate::color::Color;
+use crate::mesh::MeshData;
+use crate::vec3::Vec3;
+use crate::velem::VElem;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io(std::io::Error),
+    /// malformed header statement on `line`, counting from 1
+    Header {
+        line: usize,
+        message: String,
+    },
+    /// valid PLY this reader cannot turn into a mesh
+    Unsupported(String),
+    /// malformed or out of range element data
+    Data(String),
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Header { line, message } => write!(f, "header line {}: {}", line, message),
+            Self::Unsupported(message) => write!(f, "unsupported: {}", message),
+            Self::Data(message) => write!(f, "invalid data: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {}
+
+impl From<std::io::Error> for PlyError {
+    fn from(e: std::io::Error) -> Self {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            Self::Data("file ends before all elements were read".to_string())
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+pub fn load_ply<T: VElem>(path: impl AsRef<Path>) -> Result<MeshData<T>, PlyError> {
+    read_ply(&mut BufReader::new(File::open(path)?))
+}
+
+/// Reads the `vertex` and `face` elements of an ASCII or binary (either endianness) PLY file.
+/// Vertices need `x`, `y`, `z` and may carry `nx`, `ny`, `nz` normals, `red`, `green`, `blue`
+/// colors and `u`, `v` (or `s`, `t`) texture coordinates; faces need a `vertex_indices` list
+/// and are split into triangle fans. Other elements and scalar properties are skipped.
+pub fn read_ply<T: VElem, R: BufRead>(input: &mut R) -> Result<MeshData<T>, PlyError> {
+    let header = Header::read(input)?;
+    let mut body = Body {
+        input,
+        format: header.format,
+        tokens: Vec::new(),
+    };
+    let mut mesh = MeshData::default();
+    let mut vertex_count = None;
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let layout = VertexLayout::new(element)?;
+                for _ in 0..element.count {
+                    let values = body.read_element(element)?;
+                    let get = |i: usize| -> T { (values[i][0] as f32).into() };
+                    mesh.positions.push(Vec3::new(
+                        get(layout.xyz[0]),
+                        get(layout.xyz[1]),
+                        get(layout.xyz[2]),
+                    ));
+                    if let Some([nx, ny, nz]) = layout.normal {
+                        mesh.normals.push(Vec3::new(get(nx), get(ny), get(nz)));
+                    }
+                    if let Some([u, v]) = layout.uv {
+                        mesh.uvs.push((get(u), get(v)));
+                    }
+                    if let Some(rgb) = layout.color {
+                        let channel = |i: usize| -> T {
+                            // integer channels are 0..=255, floating point ones 0..=1
+                            match element.properties[i].kind {
+                                Kind::Scalar(Scalar::Float | Scalar::Double) => get(i),
+                                _ => get(i) / 255.0.into(),
+                            }
+                        };
+                        mesh.colors.push(Color::new(
+                            channel(rgb[0]),
+                            channel(rgb[1]),
+                            channel(rgb[2]),
+                        ));
+                    }
+                }
+                vertex_count = Some(mesh.positions.len());
+            }
+            "face" => {
+                let indices = element
+                    .properties
+                    .iter()
+                    .position(|p| p.name == "vertex_indices" || p.name == "vertex_index")
+                    .ok_or_else(|| {
+                        PlyError::Unsupported("face element without vertex_indices".to_string())
+                    })?;
+                if let Kind::Scalar(_) = element.properties[indices].kind {
+                    return Err(PlyError::Unsupported(
+                        "vertex_indices is not a list".to_string(),
+                    ));
+                }
+                for _ in 0..element.count {
+                    let values = body.read_element(element)?;
+                    let corners = &values[indices];
+                    if corners.len() < 3 {
+                        return Err(PlyError::Data(format!(
+                            "face with {} corners",
+                            corners.len()
+                        )));
+                    }
+                    // list values are read as floats, only whole non-negative ones are indices
+                    let corners = corners
+                        .iter()
+                        .map(|&c| {
+                            if c >= 0.0 && c.fract() == 0.0 {
+                                Ok(c as usize)
+                            } else {
+                                Err(PlyError::Data(format!("bad vertex index {}", c)))
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    for i in 1..corners.len() - 1 {
+                        mesh.indices.push([corners[0], corners[i], corners[i + 1]]);
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    body.read_element(element)?;
+                }
+            }
+        }
+    }
+
+    let vertex_count =
+        vertex_count.ok_or_else(|| PlyError::Unsupported("no vertex element".to_string()))?;
+    if let Some(index) = mesh.indices.iter().flatten().find(|&&i| i >= vertex_count) {
+        return Err(PlyError::Data(format!(
+            "face refers to vertex {} of {}",
+            index, vertex_count
+        )));
+    }
+    Ok(mesh)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    Char,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::Char,
+            "uchar" | "uint8" => Self::UChar,
+            "short" | "int16" => Self::Short,
+            "ushort" | "uint16" => Self::UShort,
+            "int" | "int32" => Self::Int,
+            "uint" | "uint32" => Self::UInt,
+            "float" | "float32" => Self::Float,
+            "double" | "float64" => Self::Double,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::Char | Self::UChar => 1,
+            Self::Short | Self::UShort => 2,
+            Self::Int | Self::UInt | Self::Float => 4,
+            Self::Double => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Scalar(Scalar),
+    /// list with its count type and item type
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+impl Header {
+    fn read<R: BufRead>(input: &mut R) -> Result<Self, PlyError> {
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        for number in 1.. {
+            let mut line = Vec::new();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Err(PlyError::Header {
+                    line: number,
+                    message: "file ends before end_header".to_string(),
+                });
+            }
+            let line = String::from_utf8_lossy(&line);
+            let error = |message: String| PlyError::Header {
+                line: number,
+                message,
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if number == 1 {
+                if tokens != ["ply"] {
+                    return Err(error("not a PLY file".to_string()));
+                }
+                continue;
+            }
+            match tokens.as_slice() {
+                ["format", name, version] => {
+                    if *version != "1.0" {
+                        return Err(error(format!("unknown format version {}", version)));
+                    }
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::BinaryLittleEndian,
+                        "binary_big_endian" => Format::BinaryBigEndian,
+                        _ => return Err(error(format!("unknown format '{}'", name))),
+                    });
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count
+                        .parse()
+                        .map_err(|_| error(format!("invalid element count '{}'", count)))?,
+                    properties: Vec::new(),
+                }),
+                ["property", rest @ ..] => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| error("property before the first element".to_string()))?;
+                    let scalar = |name: &str| {
+                        Scalar::parse(name)
+                            .ok_or_else(|| error(format!("unknown property type '{}'", name)))
+                    };
+                    let (kind, name) = match rest {
+                        ["list", count, item, name] => {
+                            let count = scalar(count)?;
+                            if matches!(count, Scalar::Float | Scalar::Double) {
+                                return Err(error("list count must be an integer".to_string()));
+                            }
+                            (Kind::List(count, scalar(item)?), name)
+                        }
+                        [ty, name] => (Kind::Scalar(scalar(ty)?), name),
+                        _ => return Err(error(format!("invalid property '{}'", line.trim()))),
+                    };
+                    element.properties.push(Property {
+                        name: name.to_string(),
+                        kind,
+                    });
+                }
+                ["end_header"] => break,
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => return Err(error(format!("unknown statement '{}'", line.trim()))),
+            }
+        }
+        let format = format.ok_or(PlyError::Header {
+            line: 2,
+            message: "missing format".to_string(),
+        })?;
+        Ok(Self { format, elements })
+    }
+}
+
+/// Indices of the properties of the `vertex` element the mesh is built from
+struct VertexLayout {
+    xyz: [usize; 3],
+    normal: Option<[usize; 3]>,
+    uv: Option<[usize; 2]>,
+    color: Option<[usize; 3]>,
+}
+
+impl VertexLayout {
+    fn new(element: &Element) -> Result<Self, PlyError> {
+        let find = |name: &str| -> Result<Option<usize>, PlyError> {
+            match element.properties.iter().position(|p| p.name == name) {
+                Some(i) if matches!(element.properties[i].kind, Kind::List(..)) => Err(
+                    PlyError::Unsupported(format!("vertex property '{}' is a list", name)),
+                ),
+                index => Ok(index),
+            }
+        };
+        let all = |names: &[&str]| -> Result<Option<Vec<usize>>, PlyError> {
+            let found = names
+                .iter()
+                .map(|n| find(n))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(found.into_iter().collect())
+        };
+        let xyz = all(&["x", "y", "z"])?.ok_or_else(|| {
+            PlyError::Unsupported("vertex element without x, y and z".to_string())
+        })?;
+        let uv = match all(&["u", "v"])? {
+            Some(uv) => Some(uv),
+            None => match all(&["s", "t"])? {
+                Some(st) => Some(st),
+                None => all(&["texture_u", "texture_v"])?,
+            },
+        };
+        Ok(Self {
+            xyz: [xyz[0], xyz[1], xyz[2]],
+            normal: all(&["nx", "ny", "nz"])?.map(|n| [n[0], n[1], n[2]]),
+            uv: uv.map(|uv| [uv[0], uv[1]]),
+            color: all(&["red", "green", "blue"])?.map(|c| [c[0], c[1], c[2]]),
+        })
+    }
+}
+
+/// Element data following the header
+struct Body<'a, R: BufRead> {
+    input: &'a mut R,
+    format: Format,
+    /// rest of the current ASCII line, reversed
+    tokens: Vec<String>,
+}
+
+impl<R: BufRead> Body<'_, R> {
+    /// one value per scalar property and all items of every list property
+    fn read_element(&mut self, element: &Element) -> Result<Vec<Vec<f64>>, PlyError> {
+        element
+            .properties
+            .iter()
+            .map(|property| match property.kind {
+                Kind::Scalar(ty) => Ok(vec![self.read(ty)?]),
+                Kind::List(count, item) => {
+                    let count = self.read(count)?;
+                    if count < 0.0 {
+                        return Err(PlyError::Data(format!(
+                            "negative length of list '{}'",
+                            property.name
+                        )));
+                    }
+                    (0..count as usize).map(|_| self.read(item)).collect()
+                }
+            })
+            .collect()
+    }
+
+    fn read(&mut self, ty: Scalar) -> Result<f64, PlyError> {
+        if self.format == Format::Ascii {
+            return self.read_ascii(ty);
+        }
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..ty.size()];
+        self.input.read_exact(bytes)?;
+        if self.format == Format::BinaryBigEndian {
+            bytes.reverse();
+        }
+        // the slice has exactly the size of the type
+        let bytes: &[u8] = bytes;
+        Ok(match ty {
+            Scalar::Char => bytes[0] as i8 as f64,
+            Scalar::UChar => bytes[0] as f64,
+            Scalar::Short => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Scalar::UShort => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Scalar::Int => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Scalar::UInt => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Scalar::Float => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+            Scalar::Double => f64::from_le_bytes(bytes.try_into().unwrap()),
+        })
+    }
+
+    fn read_ascii(&mut self, ty: Scalar) -> Result<f64, PlyError> {
+        while self.tokens.is_empty() {
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(PlyError::Data(
+                    "file ends before all elements were read".to_string(),
+                ));
+            }
+            self.tokens = line.split_whitespace().rev().map(String::from).collect();
+        }
+        let token = self.tokens.pop().unwrap();
+        let invalid = || PlyError::Data(format!("invalid {:?} value '{}'", ty, token));
+        let value: f64 = token.parse().map_err(|_| invalid())?;
+        let integer = !matches!(ty, Scalar::Float | Scalar::Double);
+        if integer && value.fract() != 0.0 {
+            return Err(invalid());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+element vertex 4
+property float x
+property float y
+property float z
+property float confidence
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    /// header and body of a unit square with colored corners, split as a quad
+    fn square(format: &str, body: &[u8]) -> Vec<u8> {
+        let mut ply = format!(
+            "ply\nformat {} 1.0\ncomment made by hand\n{}",
+            format, HEADER
+        )
+        .into_bytes();
+        ply.extend_from_slice(body);
+        ply
+    }
+
+    fn binary_body(big_endian: bool) -> Vec<u8> {
+        let mut body = Vec::new();
+        let corners = [
+            [0.0f32, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+        ];
+        for (i, corner) in corners.iter().enumerate() {
+            for value in corner.iter().chain([0.5].iter()) {
+                if big_endian {
+                    body.extend_from_slice(&value.to_be_bytes());
+                } else {
+                    body.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            body.extend_from_slice(&[255, 0, 51 * i as u8]);
+        }
+        body.push(4);
+        for index in 0..4i32 {
+            if big_endian {
+                body.extend_from_slice(&index.to_be_bytes());
+            } else {
+                body.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+        body
+    }
+
+    fn check_square(mesh: &MeshData<f32>) {
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.positions[2], Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert!(mesh.normals.is_empty() && mesh.uvs.is_empty());
+        assert_eq!(mesh.colors[0], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(mesh.colors[3], Color::new(1.0, 0.0, 0.6));
+    }
+
+    #[test]
+    fn ascii_test() {
+        let body = b"0 0 0 0.5 255 0 0\n1 0 0 0.5 255 0 51\n1 1 0 0.5 255 0 102\n\
+                     0 1 0 0.5 255 0 153\n4 0 1 2 3\n";
+        let ply = square("ascii", body);
+        check_square(&read_ply(&mut ply.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn binary_test() {
+        let ply = square("binary_little_endian", &binary_body(false));
+        check_square(&read_ply(&mut ply.as_slice()).unwrap());
+        let ply = square("binary_big_endian", &binary_body(true));
+        check_square(&read_ply(&mut ply.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn normals_and_uvs_test() {
+        let ply = b"ply\nformat ascii 1.0\nelement vertex 3\nproperty double x\n\
+                    property double y\nproperty double z\nproperty float nx\nproperty float ny\n\
+                    property float nz\nproperty float s\nproperty float t\nelement face 1\n\
+                    property list uchar uint vertex_index\nend_header\n\
+                    0 0 0 0 0 1 0 0\n1 0 0 0 0 1 1 0\n0 1 0 0 0 1 0 1\n3 0 1 2\n";
+        let mesh = read_ply::<f32, _>(&mut ply.as_slice()).unwrap();
+        assert_eq!(mesh.normals, vec![Vec3::new(0.0, 0.0, 1.0); 3]);
+        assert_eq!(mesh.uvs, vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+        assert!(mesh.colors.is_empty());
+        assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |ply: &[u8]| read_ply::<f32, _>(&mut &ply[..]).err().unwrap().to_string();
+        assert_eq!(error(b"PLY\n"), "header line 1: not a PLY file");
+        assert_eq!(
+            error(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\n"),
+            "header line 4: unknown property type 'half'"
+        );
+        assert_eq!(
+            error(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty list uchar float x\nend_header\n"),
+            "unsupported: vertex property 'x' is a list"
+        );
+        assert_eq!(
+            error(
+                b"ply\nformat binary_little_endian 1.0\nelement vertex 1\nproperty float x\n\
+                    property float y\nproperty float z\nend_header\n\0\0"
+            ),
+            "invalid data: file ends before all elements were read"
+        );
+        let mut ply = square("ascii", b"0 0 0 0 0 0 0\n".repeat(4).as_slice());
+        ply.extend_from_slice(b"3 0 1 7\n");
+        assert_eq!(error(&ply), "invalid data: face refers to vertex 7 of 4");
+        // indices stored as floats have to be whole and non-negative as well
+        for (face, index) in [
+            ("3 0 1 -1\n", "-1"),
+            ("3 0 1.5 2\n", "1.5"),
+            ("3 0 nan 2\n", "NaN"),
+        ] {
+            let ply = square("ascii", b"0 0 0 0 0 0 0\n".repeat(4).as_slice());
+            let mut ply = String::from_utf8(ply)
+                .unwrap()
+                .replace("list uchar int", "list uchar float");
+            ply.push_str(face);
+            assert_eq!(
+                error(ply.as_bytes()),
+                format!("invalid data: bad vertex index {}", index)
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+use rand::Rng as _;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Parallelogram spanned by the edges `u` and `v` from the corner `q`
+pub struct Quad<T: VElem> {
+    q: Point3<T>,
+    u: Vec3<T>,
+    v: Vec3<T>,
+    /// n / (n . n) for the plane normal n, used to get the planar (alpha, beta) coordinates
+    w: Vec3<T>,
+    normal: Vec3<T>,
+    /// plane offset, the plane holds every point p with normal . p = d
+    d: T,
+    area: T,
+    material: Arc<dyn Material<T>>,
+}
+
+impl<T: VElem> Quad<T> {
+    pub fn new(q: Point3<T>, u: Vec3<T>, v: Vec3<T>, material: impl Material<T> + 'static) -> Self {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+        Self {
+            q,
+            u,
+            v,
+            w: n / n.dot(&n),
+            normal,
+            d: normal.dot(&q),
+            area: n.length(),
+            material: Arc::new(material),
+        }
+    }
+}
+
+impl<T: VElem> Hittable<T> for Quad<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        let denom = self.normal.dot(&ray.direction());
+
+        // No hit if the ray is parallel to the plane.
+        if denom.abs() < 1e-8.into() {
+            return None;
+        }
+
+        // No hit if the hit point parameter t is outside the ray interval.
+        let t = (self.d - self.normal.dot(&ray.origin())) / denom;
+        if !ray_t.contains(&t) {
+            return None;
+        }
+
+        // Determine if the hit point lies within the planar shape using its plane coordinates.
+        let intersection = ray.at(t);
+        let planar_hitpt = intersection - self.q;
+        let alpha = self.w.dot(&planar_hitpt.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar_hitpt));
+        let unit = T::zero()..=T::one();
+        if !unit.contains(&alpha) || !unit.contains(&beta) {
+            return None;
+        }
+
+        let mut hit_r = HitRecord {
+            t,
+            u: alpha,
+            v: beta,
+            p: intersection,
+            normal: self.normal,
+            front_facing: false,
+            color: None,
+            material: self.material.clone(),
+        };
+        hit_r.set_face_normal(ray, self.normal);
+
+        Some(hit_r)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        // Compute the bounding box of all four vertices.
+        let diagonal1 = Aabb::new(self.q, self.q + self.u + self.v);
+        let diagonal2 = Aabb::new(self.q + self.u, self.q + self.v);
+        diagonal1.union(&diagonal2).pad(1e-4.into())
+    }
+
+    fn pdf_value(&self, origin: Point3<T>, direction: Vec3<T>) -> T {
+        let ray = Ray::new(origin, direction);
+        let Some(hit) = self.hit(&ray, 0.0001.into()..=T::infinity()) else {
+            return T::zero();
+        };
+        // points are picked uniformly by area, dA = distance^2 / cos dw
+        let distance_squared = hit.t * hit.t * direction.length_squared();
+        let cosine = (direction.dot(&self.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.area)
+    }
+
+    fn random(&self, origin: Point3<T>, rng: &mut Rng) -> Vec3<T> {
+        let alpha: T = rng.gen_range(0.0f32..1.0).into();
+        let beta: T = rng.gen_range(0.0f32..1.0).into();
+        self.q + self.u * alpha + self.v * beta - origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Quad = super::Quad<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+
+    fn unit_quad() -> Quad {
+        // 2 x 1 rectangle on the z = -5 plane facing +z
+        Quad::new(
+            Vec3::new(-1.0, 0.0, -5.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    #[test]
+    fn test_ray_hits_quad() {
+        let quad = unit_quad();
+        let ray = Ray::new(Vec3::new(0.5, 0.25, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = quad.hit(&ray, 0.0..=10.0).expect("Ray should hit the quad");
+        assert_eq!(hit.t, 5.0);
+        assert_eq!(hit.p, Vec3::new(0.5, 0.25, -5.0));
+        assert_eq!((hit.u, hit.v), (0.75, 0.25));
+        assert!(hit.front_facing);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_ray_hits_back_face() {
+        let quad = unit_quad();
+        let ray = Ray::new(Vec3::new(0.0, 0.5, -10.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = quad.hit(&ray, 0.0..=10.0).expect("Ray should hit the quad");
+        assert!(!hit.front_facing);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_ray_misses_quad() {
+        let quad = unit_quad();
+        // plane is hit outside of the parallelogram
+        let ray = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(quad.hit(&ray, 0.0..=10.0).is_none());
+        // parallel to the plane
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(quad.hit(&ray, 0.0..=10.0).is_none());
+        // out of range
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(quad.hit(&ray, 0.0..=4.0).is_none());
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let bbox = unit_quad().bounding_box();
+        let e = bbox.extent();
+        assert_eq!((e.x(), e.y()), (2.0, 1.0));
+        // flat side is padded so the box keeps some volume
+        assert!(e.z() > 0.0 && e.z() < 1e-3);
+        assert_eq!(bbox.centroid().x(), 0.0);
+        let ray = Ray::new(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(bbox.hit(&ray, 0.0..=10.0));
+    }
+}
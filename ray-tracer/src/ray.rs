@@ -1,12 +1,22 @@
+use crate::background::Background;
 use crate::color::Color;
 use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::material::Scattered;
+use crate::pdf::{HittablePdf, MixturePdf, Pdf};
+use crate::rng::Rng;
 use crate::vec3::{Point3, Vec3};
 use crate::velem::VElem;
+use rand::Rng as _;
 
 #[derive(Default, Clone, Copy)]
 pub struct Ray<T: VElem> {
     origin: Point3<T>,
     direction: Vec3<T>,
+    time: T,
+    /// Bits drawn from the path's generator for objects that make random choices in
+    /// [`Hittable::hit`], which has no generator of its own
+    random: u64,
 }
 
 impl<T: VElem> Ray<T> {
@@ -20,31 +30,99 @@ impl<T: VElem> Ray<T> {
         self.direction
     }
 
+    /// moment within the shutter interval the ray was sent at, moving objects are hit where
+    /// they are at that time
+    #[inline]
+    pub fn time(&self) -> T {
+        self.time
+    }
+
+    #[inline]
+    pub fn random(&self) -> u64 {
+        self.random
+    }
+
+    /// same ray carrying other random bits, [`Ray::color`] sets fresh ones before every trace
+    pub fn with_random(self, random: u64) -> Self {
+        Self { random, ..self }
+    }
+
     pub fn at(&self, time: T) -> Point3<T> {
         self.origin + (self.direction * time)
     }
     pub fn new(origin: impl Into<Point3<T>>, direction: impl Into<Vec3<T>>) -> Self {
+        Self::with_time(origin, direction, T::zero())
+    }
+
+    pub fn with_time(origin: impl Into<Point3<T>>, direction: impl Into<Vec3<T>>, time: T) -> Self {
         Self {
             origin: origin.into(),
             direction: direction.into(),
+            time,
+            random: 0,
         }
     }
 
-    pub fn color(&self, world: &impl Hittable<T>, depth: u16) -> Color<T> {
+    pub fn color(
+        &self,
+        world: &impl Hittable<T>,
+        depth: u16,
+        background: &Background<T>,
+        lights: &HittableList<T>,
+        rng: &mut Rng,
+    ) -> Color<T> {
+        // out of bounces, no more light is gathered along this path
         if depth == 0 {
-            return Color::from([T::one(); 3]);
+            return Color::from([T::zero(); 3]);
         }
-        if let Some(hr) = world.hit(self, Into::<T>::into(0.0001)..=T::max_value()) {
-            if let Some((scattered, attenuation)) = hr.material.scatter(self, &hr) {
-                return attenuation * scattered.color(world, depth - 1);
-            } else {
-                return Color::from([T::zero(); 3]);
+        let ray = self.with_random(rng.gen());
+        if let Some(hr) = world.hit(&ray, Into::<T>::into(0.0001)..=T::max_value()) {
+            let emitted = hr.material.emitted(hr.u, hr.v, hr.p);
+            let Some(srec) = hr.material.scatter(self, &hr, rng) else {
+                return emitted;
+            };
+            let material_pdf = match srec.scattered {
+                Scattered::Specular(scattered) => {
+                    return emitted
+                        + srec.attenuation
+                            * scattered.color(world, depth - 1, background, lights, rng);
+                }
+                Scattered::Pdf(pdf) => pdf,
+            };
+            // half of the samples go towards the light sources and the bright parts of the
+            // background, the material density keeps every direction it scatters into covered
+            let objects = (!lights.is_empty()).then(|| HittablePdf::new(lights, hr.p));
+            let (light_mixture, mixture);
+            let light: Option<&dyn Pdf<T>> = match (&objects, background.pdf()) {
+                (Some(objects), Some(environment)) => {
+                    light_mixture = MixturePdf::new(objects, environment);
+                    Some(&light_mixture)
+                }
+                (Some(objects), None) => Some(objects),
+                (None, environment) => environment,
+            };
+            let pdf: &dyn Pdf<T> = match light {
+                Some(light) => {
+                    mixture = MixturePdf::new(material_pdf.as_ref(), light);
+                    &mixture
+                }
+                None => material_pdf.as_ref(),
+            };
+            // Monte Carlo estimate of the scattered light, brdf * cos / pdf for the sampled
+            // direction, where the brdf times the cosine is attenuation * scattering_pdf
+            let scattered = Ray::with_time(hr.p, pdf.generate(rng), self.time());
+            let pdf_value = pdf.value(scattered.direction());
+            let scattering_pdf = hr.material.scattering_pdf(self, &hr, &scattered);
+            // light samples may point into the surface, where nothing is scattered
+            if pdf_value <= T::zero() || scattering_pdf <= T::zero() {
+                return emitted;
             }
+            return emitted
+                + srec.attenuation
+                    * scattered.color(world, depth - 1, background, lights, rng)
+                    * (scattering_pdf / pdf_value);
         }
-        let unit_direction = self.direction().unit_vector();
-        let a: T = (unit_direction.y() + T::one()) * 0.5.into();
-        Color::from([T::one(); 3]) * (T::one() - a)
-            + Color::from([0.5.into(), 0.7.into(), T::one()]) * a
+        background.color(self)
     }
 }
 
@@ -64,6 +142,214 @@ mod impl_tests {
         assert_eq!(r.direction().x(), 2.0);
         assert_eq!(r.direction().y(), 1.0);
         assert_eq!(r.direction().z(), 1.0);
+        assert_eq!(r.time(), 0.0);
+
+        let r = Ray::with_time([1.0, 1.0, 1.0], [2.0, 1.0, 1.0], 0.25);
+        assert_eq!(r.time(), 0.25);
+    }
+
+    #[test]
+    fn color_emission_tests() {
+        use crate::diffuse_light::DiffuseLight;
+        use crate::hittable_list::HittableList;
+        use crate::sphere::Sphere;
+        use rand::SeedableRng;
+        use std::sync::Arc;
+
+        let world: HittableList<f32> = vec![Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -5.0),
+            1.0,
+            DiffuseLight::new(Color::new(4.0, 2.0, 1.0)),
+        ))];
+        let r = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, -1.0]);
+        let bg = Background::black();
+        let mut rng = Rng::seed_from_u64(0);
+        assert_eq!(
+            r.color(&world, 5, &bg, &Vec::new(), &mut rng),
+            Color::new(4.0, 2.0, 1.0)
+        );
+        // no light is gathered once the bounces run out
+        assert_eq!(
+            r.color(&world, 0, &bg, &Vec::new(), &mut rng),
+            Color::new(0.0, 0.0, 0.0)
+        );
+        // rays escaping the scene pick up the background
+        let bg = Background::Solid(Color::new(0.5, 0.5, 0.5));
+        let r = Ray::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+        assert_eq!(
+            r.color(&world, 5, &bg, &Vec::new(), &mut rng),
+            Color::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn color_importance_sampling() {
+        use crate::lambertian::Lambertian;
+        use crate::sphere::Sphere;
+        use rand::SeedableRng;
+        use std::sync::Arc;
+
+        // A diffuse convex object under a uniform white sky reflects exactly its albedo.
+        // With cosine sampling brdf * cos / pdf cancels down to the albedo for every sample.
+        let world: crate::hittable_list::HittableList<f32> = vec![Arc::new(Sphere::new(
+            Point3::new(0.0, 0.0, -5.0),
+            1.0,
+            Lambertian::new(Color::new(0.5, 0.25, 1.0)),
+        ))];
+        let bg = Background::Solid(Color::new(1.0, 1.0, 1.0));
+        let mut rng = Rng::seed_from_u64(0);
+        for i in 0..50 {
+            let r = Ray::new([0.0, 0.0, 0.0], [i as f32 * 0.004, 0.0, -1.0]);
+            let c = r.color(&world, 5, &bg, &Vec::new(), &mut rng);
+            assert!((c - Color::new(0.5, 0.25, 1.0)).length() < 1e-4, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn color_samples_environment() {
+        use crate::environment::EnvironmentMap;
+        use crate::image::Image;
+        use crate::lambertian::Lambertian;
+        use crate::pdf::CosinePdf;
+        use crate::quad::Quad;
+        use rand::SeedableRng;
+        use std::sync::Arc;
+
+        // dim sky with a small, very bright sun above a diffuse floor
+        let mut image = Image::new(32, 16);
+        for p in image.pixels_mut() {
+            *p = Color::new(0.1, 0.1, 0.1);
+        }
+        image[(20, 5)] = Color::new(5000.0, 5000.0, 5000.0);
+        let map = Arc::new(EnvironmentMap::new(image).unwrap());
+        let world: crate::hittable_list::HittableList<f32> = vec![Arc::new(Quad::new(
+            Point3::new(-10.0, 0.0, 10.0),
+            Vec3::new(20.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -20.0),
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        ))];
+        let r = Ray::new([0.0, 1.0, 0.0], [0.0, -1.0, 0.0]);
+        let n = 4000;
+        let stats = |samples: &[f32]| {
+            let mean = samples.iter().sum::<f32>() / n as f32;
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n as f32;
+            (mean, variance)
+        };
+
+        let mut rng = Rng::seed_from_u64(3);
+        let bg = Background::Environment(map.clone());
+        let mixed: Vec<_> = (0..n)
+            .map(|_| r.color(&world, 2, &bg, &Vec::new(), &mut rng).x())
+            .collect();
+        // the same estimate from cosine sampling alone, brdf * cos / pdf is the albedo
+        let cosine = CosinePdf::new(Vec3::new(0.0, 1.0, 0.0));
+        let cosine_only: Vec<_> = (0..n)
+            .map(|_| 0.5 * map.radiance(cosine.generate(&mut rng)).x())
+            .collect();
+
+        // albedo / pi times the integral of the radiance times the cosine over the upper half
+        let m = 512;
+        let mut expected = 0.0;
+        for i in 0..m {
+            for j in 0..m / 2 {
+                let phi = (i as f32 + 0.5) / m as f32 * std::f32::consts::TAU;
+                let theta = (j as f32 + 0.5) / m as f32 * std::f32::consts::PI;
+                let d = Vec3::new(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    -theta.sin() * phi.cos(),
+                );
+                let d_omega =
+                    theta.sin() * std::f32::consts::PI * std::f32::consts::TAU / (m * m) as f32;
+                expected += 0.5 / std::f32::consts::PI * map.radiance(d).x() * d.y() * d_omega;
+            }
+        }
+
+        let (mixed_mean, mixed_variance) = stats(&mixed);
+        let (_, cosine_variance) = stats(&cosine_only);
+        assert!(
+            (mixed_mean - expected).abs() < 0.05 * expected,
+            "{} != {}",
+            mixed_mean,
+            expected
+        );
+        assert!(
+            mixed_variance * 10.0 < cosine_variance,
+            "{} vs {}",
+            mixed_variance,
+            cosine_variance
+        );
+    }
+
+    #[test]
+    fn color_samples_lights() {
+        use crate::diffuse_light::DiffuseLight;
+        use crate::lambertian::Lambertian;
+        use crate::quad::Quad;
+        use rand::SeedableRng;
+        use std::sync::Arc;
+
+        // small bright ceiling light above a diffuse floor in the dark
+        let light: Arc<dyn Hittable<f32>> = Arc::new(Quad::new(
+            Point3::new(-0.2, 2.0, -0.2),
+            Vec3::new(0.4, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.4),
+            DiffuseLight::new(Color::new(50.0, 50.0, 50.0)),
+        ));
+        let world: HittableList<f32> = vec![
+            Arc::new(Quad::new(
+                Point3::new(-10.0, 0.0, 10.0),
+                Vec3::new(20.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, -20.0),
+                Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+            )),
+            light.clone(),
+        ];
+        let r = Ray::new([0.5, 1.0, 0.5], [0.0, -1.0, 0.0]);
+        let bg = Background::black();
+        let n = 4000;
+        let render = |lights: &HittableList<f32>| {
+            let mut rng = Rng::seed_from_u64(5);
+            let samples: Vec<_> = (0..n)
+                .map(|_| r.color(&world, 2, &bg, lights, &mut rng).x())
+                .collect();
+            let mean = samples.iter().sum::<f32>() / n as f32;
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / n as f32;
+            (mean, variance)
+        };
+
+        // albedo / pi times the light seen from the floor point, summed over the light's area
+        let m = 100;
+        let mut expected = 0.0;
+        for i in 0..m {
+            for j in 0..m {
+                let p = Point3::new(
+                    -0.2 + 0.4 * (i as f32 + 0.5) / m as f32,
+                    2.0,
+                    -0.2 + 0.4 * (j as f32 + 0.5) / m as f32,
+                );
+                let d = p - Point3::new(0.5, 0.0, 0.5);
+                let cos = d.y() / d.length();
+                let d_area = 0.16 / (m * m) as f32;
+                expected +=
+                    0.5 / std::f32::consts::PI * 50.0 * cos * cos / d.length_squared() * d_area;
+            }
+        }
+
+        let (sampled_mean, sampled_variance) = render(&vec![light]);
+        let (_, cosine_variance) = render(&Vec::new());
+        assert!(
+            (sampled_mean - expected).abs() < 0.05 * expected,
+            "{} != {}",
+            sampled_mean,
+            expected
+        );
+        assert!(
+            sampled_variance * 10.0 < cosine_variance,
+            "{} vs {}",
+            sampled_variance,
+            cosine_variance
+        );
     }
 
     #[test]
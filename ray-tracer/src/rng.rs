@@ -0,0 +1,52 @@
+/// Generator behind all random sampling.
+/// PCG is small and fast, and produces the same sequence for a seed on every platform.
+pub type Rng = rand_pcg::Pcg32;
+
+/// Generator for the samples of pixel (`x`, `y`) of an image `width` pixels wide.
+/// Every pixel gets its own stream, so the image does not depend on which thread renders what.
+pub fn pixel_rng(seed: u64, width: u64, x: u64, y: u64) -> Rng {
+    Rng::new(splitmix64(seed), y * width + x)
+}
+
+/// Uniform number in [0, 1) computed from `bits` alone, for the few places that need a random
+/// draw but have no [`Rng`] at hand. Equal inputs give equal numbers, so renders stay reproducible.
+pub fn hash_unit(bits: &[u64]) -> f64 {
+    let hash = bits
+        .iter()
+        .fold(0u64, |acc, b| splitmix64(acc ^ splitmix64(*b)));
+    // the top 53 bits fill the mantissa of a double
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// SplitMix64 finalizer, spreads nearby seeds over the whole state space
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn pixel_rng_test() {
+        let first = |mut rng: Rng| rng.next_u64();
+        assert_eq!(first(pixel_rng(7, 10, 3, 2)), first(pixel_rng(7, 10, 3, 2)));
+        assert_ne!(first(pixel_rng(7, 10, 3, 2)), first(pixel_rng(8, 10, 3, 2)));
+        assert_ne!(first(pixel_rng(7, 10, 3, 2)), first(pixel_rng(7, 10, 4, 2)));
+    }
+
+    #[test]
+    fn hash_unit_test() {
+        assert_eq!(hash_unit(&[1, 2, 3]), hash_unit(&[1, 2, 3]));
+        assert_ne!(hash_unit(&[1, 2, 3]), hash_unit(&[1, 2, 4]));
+        assert_ne!(hash_unit(&[1, 2, 3]), hash_unit(&[3, 2, 1]));
+        let mean = (0..1000).map(|i| hash_unit(&[i])).sum::<f64>() / 1000.0;
+        assert!((0.45..0.55).contains(&mean));
+        assert!((0..1000).all(|i| (0.0..1.0).contains(&hash_unit(&[i, 7]))));
+    }
+}
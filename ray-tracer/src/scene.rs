@@ -0,0 +1,809 @@
+//! Declarative TOML scene description.
+//!
+//! ```toml
+//! [camera]
+//! image_width = 400
+//! look_from = [0, 0, 0]
+//! look_at = [0, 0, -1]
+//!
+//! [background]
+//! type = "sky"
+//!
+//! [materials.red]
+//! type = "lambertian"
+//! albedo = [0.8, 0.1, 0.1]
+//!
+//! [[objects]]
+//! type = "sphere"
+//! center = [0, 0, -1]
+//! radius = 0.5
+//! material = "red"
+//! ```
+//!
+//! Materials are shared by name between the objects referencing them. The `albedo` of
+//! lambertian and metal materials is either a color or a texture table, e.g.
+//! `albedo = { type = "checker", scale = 0.5, even = [0, 0, 0], odd = [1, 1, 1] }` or
+//! `albedo = { type = "image", path = "earth.png" }`. Procedural noise textures take a `pattern`
+//! of "noise", "turbulence", "marble" or "wood" plus a `scale`, an optional `color` and `seed`.
+//!
+//! Every object can be placed with an optional
+//! `transform = { scale = .., rotate = [x, y, z], translate = [..] }`, applied in that order
+//! with the rotation angles in degrees around X, then Y, then Z. Spheres given a `center_end`
+//! move there from `center` over the time 0 to 1, which the camera samples within its
+//! `shutter = [open, close]` for motion blur, with 0 <= open <= close <= 1.
+//!
+//! Quads and spheres with a `diffuse_light` material are sampled directly as light sources,
+//! unless they move or have a transform.
+//!
+//! Relative paths are resolved against the directory of the scene file.
+
+use crate::background::Background;
+use crate::camera::{Camera, CameraBuilder};
+use crate::cuboid::Cuboid;
+use crate::dielectric::Dielectric;
+use crate::diffuse_light::DiffuseLight;
+use crate::environment::EnvironmentMap;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::instance::Instance;
+use crate::lambertian::Lambertian;
+use crate::material::Material;
+use crate::mesh::TriangleMesh;
+use crate::metal::Metal;
+use crate::quad::Quad;
+use crate::sphere::Sphere;
+use crate::texture::{Checker, ImageTexture, NoisePattern, NoiseTexture, SolidColor, Texture};
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+use crate::velem::VElem;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use toml::Spanned;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    /// invalid scene description on `line`, counting from 1
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Objects and camera settings read from a scene file.
+/// The camera is left as a builder so that its settings can still be overridden before rendering.
+pub struct Scene<T: VElem> {
+    pub camera: CameraBuilder<T>,
+    pub world: HittableList<T>,
+}
+
+impl<T: VElem> Scene<T> {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// parses a scene description, relative paths in it are resolved against `base_dir`
+    pub fn parse(source: &str, base_dir: &Path) -> Result<Self, SceneError> {
+        let error = |span: Option<std::ops::Range<usize>>, message: String| SceneError::Parse {
+            line: span.map_or(1, |span| line_of(source, span.start)),
+            message,
+        };
+        let desc: SceneDesc =
+            toml::from_str(source).map_err(|e| error(e.span(), e.message().to_string()))?;
+
+        let materials: HashMap<String, Arc<dyn Material<T>>> = desc
+            .materials
+            .iter()
+            .map(|(name, material)| {
+                let built = material
+                    .get_ref()
+                    .build(base_dir)
+                    .map_err(|message| error(Some(material.span()), message))?;
+                Ok((name.clone(), built))
+            })
+            .collect::<Result<_, SceneError>>()?;
+
+        let emits = |name: &String| {
+            desc.materials
+                .get(name)
+                .is_some_and(|m| matches!(m.get_ref(), MaterialDesc::DiffuseLight { .. }))
+        };
+        let mut world: HittableList<T> = Vec::new();
+        let mut lights: HittableList<T> = Vec::new();
+        for object in &desc.objects {
+            let span = Some(object.span());
+            let material = |name: &String| {
+                materials
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| error(span.clone(), format!("unknown material '{}'", name)))
+            };
+            let (hittable, transform): (Arc<dyn Hittable<T>>, _) = match object.get_ref() {
+                ObjectDesc::Sphere {
+                    center,
+                    center_end,
+                    radius,
+                    material: name,
+                    transform,
+                } => (
+                    Arc::new(Sphere::moving(
+                        vec3(*center),
+                        vec3(center_end.unwrap_or(*center)),
+                        (*radius).into(),
+                        material(name)?,
+                    )),
+                    transform,
+                ),
+                ObjectDesc::Quad {
+                    q,
+                    u,
+                    v,
+                    material: name,
+                    transform,
+                } => (
+                    Arc::new(Quad::new(vec3(*q), vec3(*u), vec3(*v), material(name)?)),
+                    transform,
+                ),
+                ObjectDesc::Box {
+                    min,
+                    max,
+                    material: name,
+                    transform,
+                } => {
+                    if (0..3).any(|axis| min[axis] == max[axis]) {
+                        return Err(error(span, "box is flat along an axis".to_string()));
+                    }
+                    (
+                        Arc::new(Cuboid::new(vec3(*min), vec3(*max), material(name)?)),
+                        transform,
+                    )
+                }
+                ObjectDesc::Triangle {
+                    a,
+                    b,
+                    c,
+                    material: name,
+                    transform,
+                } => (
+                    Arc::new(Triangle::new(vec3(*a), vec3(*b), vec3(*c), material(name)?)),
+                    transform,
+                ),
+                ObjectDesc::Obj { path, transform } => {
+                    let meshes = crate::obj::load_obj(base_dir.join(path)).map_err(|e| {
+                        error(
+                            span.clone(),
+                            format!("cannot load '{}': {}", path.display(), e),
+                        )
+                    })?;
+                    (Arc::new(meshes), transform)
+                }
+                ObjectDesc::Ply {
+                    path,
+                    material: name,
+                    smooth,
+                    transform,
+                } => {
+                    let mut mesh = crate::ply::load_ply(base_dir.join(path)).map_err(|e| {
+                        error(
+                            span.clone(),
+                            format!("cannot load '{}': {}", path.display(), e),
+                        )
+                    })?;
+                    if *smooth && mesh.normals.is_empty() {
+                        mesh.compute_normals();
+                    }
+                    let mesh = match name {
+                        Some(name) => TriangleMesh::new(mesh, material(name)?),
+                        None if !mesh.colors.is_empty() => TriangleMesh::vertex_colored(mesh),
+                        None => {
+                            return Err(error(
+                                span,
+                                format!(
+                                    "'{}' has no vertex colors, it needs a material",
+                                    path.display()
+                                ),
+                            ))
+                        }
+                    };
+                    (Arc::new(mesh), transform)
+                }
+            };
+            // emitting quads and still spheres in place can be sampled as lights
+            let light = match object.get_ref() {
+                ObjectDesc::Sphere {
+                    center_end: None,
+                    material,
+                    transform: None,
+                    ..
+                }
+                | ObjectDesc::Quad {
+                    material,
+                    transform: None,
+                    ..
+                } => emits(material),
+                _ => false,
+            };
+            if light {
+                lights.push(hittable.clone());
+            }
+            world.push(match transform {
+                Some(transform) => {
+                    let transform = transform.build().map_err(|e| error(span, e))?;
+                    Arc::new(Instance::new(hittable, transform))
+                }
+                None => hittable,
+            });
+        }
+
+        if let Some(shutter) = &desc.camera.shutter {
+            let (open, close) = *shutter.get_ref();
+            // moving objects are only defined from time 0 to 1
+            if !(0.0..=1.0).contains(&open) || !(0.0..=1.0).contains(&close) || open > close {
+                return Err(error(
+                    Some(shutter.span()),
+                    "shutter times must satisfy 0 <= open <= close <= 1".to_string(),
+                ));
+            }
+        }
+        let mut camera = desc.camera.builder().lights(lights);
+        if let Some(background) = desc.background {
+            let background = match background.get_ref() {
+                BackgroundDesc::Sky => Background::sky(),
+                BackgroundDesc::Solid { color } => Background::Solid(vec3(*color)),
+                BackgroundDesc::Gradient { bottom, top } => Background::Gradient {
+                    bottom: vec3(*bottom),
+                    top: vec3(*top),
+                },
+                BackgroundDesc::Environment { path } => {
+                    let map = EnvironmentMap::open(base_dir.join(path)).map_err(|e| {
+                        error(
+                            Some(background.span()),
+                            format!("cannot load '{}': {}", path.display(), e),
+                        )
+                    })?;
+                    Background::Environment(Arc::new(map))
+                }
+            };
+            camera = camera.background(background);
+        }
+
+        Ok(Self { camera, world })
+    }
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+fn vec3<T: VElem>([x, y, z]: [f32; 3]) -> Vec3<T> {
+    Vec3::new(x.into(), y.into(), z.into())
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+    #[serde(default)]
+    camera: CameraDesc,
+    background: Option<Spanned<BackgroundDesc>>,
+    #[serde(default)]
+    materials: HashMap<String, Spanned<MaterialDesc>>,
+    #[serde(default)]
+    objects: Vec<Spanned<ObjectDesc>>,
+}
+
+/// unset settings keep the [`CameraBuilder`] defaults
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    aspect_ratio: Option<(u8, u8)>,
+    image_width: Option<u64>,
+    image_height: Option<u64>,
+    samples_per_pixel: Option<u16>,
+    max_depth: Option<u16>,
+    vfov: Option<f32>,
+    look_from: Option<[f32; 3]>,
+    look_at: Option<[f32; 3]>,
+    vup: Option<[f32; 3]>,
+    defocus_angle: Option<f32>,
+    focus_dist: Option<f32>,
+    seed: Option<u64>,
+    shutter: Option<Spanned<(f32, f32)>>,
+}
+
+impl CameraDesc {
+    fn builder<T: VElem>(&self) -> CameraBuilder<T> {
+        let mut camera = Camera::builder();
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            camera = camera.aspect_ratio(aspect_ratio);
+        }
+        if let Some(image_width) = self.image_width {
+            camera = camera.image_width(image_width);
+        }
+        if let Some(image_height) = self.image_height {
+            camera = camera.image_height(image_height);
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            camera = camera.samples_per_pixel(samples_per_pixel);
+        }
+        if let Some(max_depth) = self.max_depth {
+            camera = camera.max_depth(max_depth);
+        }
+        if let Some(vfov) = self.vfov {
+            camera = camera.vfov(vfov.into());
+        }
+        if let Some(look_from) = self.look_from {
+            camera = camera.look_from(vec3(look_from));
+        }
+        if let Some(look_at) = self.look_at {
+            camera = camera.look_at(vec3(look_at));
+        }
+        if let Some(vup) = self.vup {
+            camera = camera.vup(vec3(vup));
+        }
+        if let Some(defocus_angle) = self.defocus_angle {
+            camera = camera.defocus_angle(defocus_angle.into());
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            camera = camera.focus_dist(focus_dist.into());
+        }
+        if let Some(seed) = self.seed {
+            camera = camera.seed(seed);
+        }
+        if let Some(shutter) = &self.shutter {
+            let (open, close) = *shutter.get_ref();
+            camera = camera.shutter(open.into(), close.into());
+        }
+        camera
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    Sky,
+    Solid { color: [f32; 3] },
+    Gradient { bottom: [f32; 3], top: [f32; 3] },
+    Environment { path: PathBuf },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian { albedo: AlbedoDesc },
+    Metal { albedo: AlbedoDesc, fuzz: f32 },
+    Dielectric { refraction_index: f32 },
+    DiffuseLight { emit: [f32; 3] },
+}
+
+impl MaterialDesc {
+    fn build<T: VElem>(&self, base_dir: &Path) -> Result<Arc<dyn Material<T>>, String> {
+        Ok(match self {
+            Self::Lambertian { albedo } => Arc::new(Lambertian::textured(albedo.build(base_dir)?)),
+            Self::Metal { albedo, fuzz } => {
+                Arc::new(Metal::textured(albedo.build(base_dir)?, (*fuzz).into()))
+            }
+            Self::Dielectric { refraction_index } => {
+                Arc::new(Dielectric::new((*refraction_index).into()))
+            }
+            Self::DiffuseLight { emit } => Arc::new(DiffuseLight::new(vec3::<T>(*emit))),
+        })
+    }
+}
+
+/// a plain color or a texture table
+enum AlbedoDesc {
+    Color([f32; 3]),
+    Texture(TextureDesc),
+}
+
+/// Picks the variant from the TOML value type instead of going through `#[serde(untagged)]`,
+/// which would replace the errors in a texture table with a generic one.
+impl<'de> Deserialize<'de> for AlbedoDesc {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = AlbedoDesc;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color or a texture table")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let value = serde::de::value::SeqAccessDeserializer::new(seq);
+                Ok(AlbedoDesc::Color(Deserialize::deserialize(value)?))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let value = serde::de::value::MapAccessDeserializer::new(map);
+                Ok(AlbedoDesc::Texture(Deserialize::deserialize(value)?))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+    Solid {
+        color: [f32; 3],
+    },
+    Checker {
+        scale: f32,
+        even: [f32; 3],
+        odd: [f32; 3],
+    },
+    Image {
+        path: PathBuf,
+    },
+    Noise {
+        pattern: NoisePattern,
+        scale: f32,
+        #[serde(default = "white")]
+        color: [f32; 3],
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+fn white() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl AlbedoDesc {
+    fn build<T: VElem>(&self, base_dir: &Path) -> Result<Arc<dyn Texture<T>>, String> {
+        Ok(match self {
+            Self::Color(color) | Self::Texture(TextureDesc::Solid { color }) => {
+                Arc::new(SolidColor::new(vec3(*color)))
+            }
+            Self::Texture(TextureDesc::Checker { scale, even, odd }) => {
+                Arc::new(Checker::colors((*scale).into(), vec3(*even), vec3(*odd)))
+            }
+            Self::Texture(TextureDesc::Image { path }) => Arc::new(
+                ImageTexture::open(base_dir.join(path))
+                    .map_err(|e| format!("cannot load '{}': {}", path.display(), e))?,
+            ),
+            Self::Texture(TextureDesc::Noise {
+                pattern,
+                scale,
+                color,
+                seed,
+            }) => Arc::new(NoiseTexture::with_seed(
+                *pattern,
+                (*scale).into(),
+                vec3(*color),
+                *seed,
+            )),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        center: [f32; 3],
+        /// where the center is at time 1, for motion blur
+        center_end: Option<[f32; 3]>,
+        radius: f32,
+        material: String,
+        transform: Option<TransformDesc>,
+    },
+    Quad {
+        q: [f32; 3],
+        u: [f32; 3],
+        v: [f32; 3],
+        material: String,
+        transform: Option<TransformDesc>,
+    },
+    /// axis aligned box between two opposite corners
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+        material: String,
+        transform: Option<TransformDesc>,
+    },
+    Triangle {
+        a: [f32; 3],
+        b: [f32; 3],
+        c: [f32; 3],
+        material: String,
+        transform: Option<TransformDesc>,
+    },
+    /// Wavefront OBJ file with the materials of its MTL libraries
+    Obj {
+        path: PathBuf,
+        transform: Option<TransformDesc>,
+    },
+    /// PLY mesh, drawn with its vertex colors if it has some and no material is given
+    Ply {
+        path: PathBuf,
+        material: Option<String>,
+        /// interpolate normals computed from the faces if the file has none
+        #[serde(default)]
+        smooth: bool,
+        transform: Option<TransformDesc>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDesc {
+    scale: Option<ScaleDesc>,
+    rotate: Option<[f32; 3]>,
+    translate: Option<[f32; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScaleDesc {
+    Uniform(f32),
+    PerAxis([f32; 3]),
+}
+
+impl TransformDesc {
+    fn build<T: VElem>(&self) -> Result<Transform<T>, String> {
+        let mut transform = Transform::identity();
+        if let Some(scale) = &self.scale {
+            let factors = match scale {
+                ScaleDesc::Uniform(s) => [*s; 3],
+                ScaleDesc::PerAxis(factors) => *factors,
+            };
+            // a zero factor flattens the object and leaves the transform without an inverse
+            if factors.contains(&0.0) {
+                return Err("scale factors must be non-zero".to_string());
+            }
+            transform = transform.then(&Transform::scale(vec3(factors)));
+        }
+        if let Some(degrees) = self.rotate {
+            for (axis, degrees) in degrees.into_iter().enumerate() {
+                let mut direction = [0.0; 3];
+                direction[axis] = 1.0;
+                transform = transform.then(&Transform::rotate(vec3(direction), degrees.into()));
+            }
+        }
+        if let Some(offset) = self.translate {
+            transform = transform.then(&Transform::translate(vec3(offset)));
+        }
+        Ok(transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+
+    const SCENE: &str = r#"
+[camera]
+image_width = 64
+aspect_ratio = [1, 1]
+look_from = [0, 0, 1]
+look_at = [0, 0, -1]
+
+[background]
+type = "solid"
+color = [0, 0, 0]
+
+[materials.red]
+type = "lambertian"
+albedo = [0.8, 0.1, 0.1]
+
+[materials.mirror]
+type = "metal"
+albedo = [0.9, 0.9, 0.9]
+fuzz = 0.0
+
+[[objects]]
+type = "sphere"
+center = [0, 0, -1]
+radius = 0.5
+material = "red"
+
+[[objects]]
+type = "quad"
+q = [-1, -1, -3]
+u = [2, 0, 0]
+v = [0, 2, 0]
+material = "mirror"
+transform = { scale = 2, translate = [5, 0, 0] }
+"#;
+
+    fn error(source: &str) -> String {
+        match Scene::<f32>::parse(source, Path::new("")) {
+            Ok(_) => panic!("scene should not load"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn load_scene() {
+        let scene = Scene::<f32>::parse(SCENE, Path::new("")).unwrap();
+        assert_eq!(scene.world.len(), 2);
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&ray, 0.0..=100.0).unwrap();
+        assert!((hit.t - 1.5).abs() < 1e-6);
+
+        // the quad got scaled to 4 x 4 and moved to x = 5
+        let ray = Ray::new(Vec3::new(6.5, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&ray, 0.0..=100.0).unwrap();
+        assert!((hit.t - 6.0).abs() < 1e-5);
+
+        let camera = scene.camera.build();
+        let image = camera.render(&scene.world);
+        assert_eq!((image.width(), image.height()), (64, 64));
+    }
+
+    #[test]
+    fn empty_scene_uses_defaults() {
+        let scene = Scene::<f32>::parse("", Path::new("")).unwrap();
+        assert!(scene.world.is_empty());
+    }
+
+    #[test]
+    fn unknown_material() {
+        let source = SCENE.replace("material = \"mirror\"", "material = \"gold\"");
+        assert_eq!(error(&source), "line 27: unknown material 'gold'");
+    }
+
+    #[test]
+    fn syntax_errors_have_line_numbers() {
+        let source = SCENE.replace("radius = 0.5", "radius = \"big\"");
+        assert!(
+            error(&source).starts_with("line 21: "),
+            "{}",
+            error(&source)
+        );
+
+        let source = SCENE.replace("fuzz = 0.0", "fuzziness = 0.0");
+        assert!(
+            error(&source).starts_with("line 16: "),
+            "{}",
+            error(&source)
+        );
+
+        assert!(error("[camera]\nimage_width = 64\nimage_width = 32\n").starts_with("line 3: "));
+    }
+
+    #[test]
+    fn missing_mesh_file() {
+        let source = "[[objects]]\ntype = \"ply\"\npath = \"missing.ply\"\n";
+        assert!(error(source).starts_with("line 1: cannot load 'missing.ply'"));
+    }
+
+    #[test]
+    fn box_object() {
+        let source = r#"
+[materials.white]
+type = "lambertian"
+albedo = [0.7, 0.7, 0.7]
+
+[[objects]]
+type = "box"
+min = [0, 0, -3]
+max = [1, 2, -2]
+material = "white"
+transform = { translate = [1, 0, 0] }
+"#;
+        let scene = Scene::<f32>::parse(source, Path::new("")).unwrap();
+        let ray = Ray::new(Vec3::new(1.5, 1.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.world.hit(&ray, 0.0..=100.0).unwrap();
+        assert!((hit.t - 2.0).abs() < 1e-5);
+        assert!(hit.front_facing);
+
+        let flat = source.replace("max = [1, 2, -2]", "max = [1, 0, -2]");
+        assert_eq!(error(&flat), "line 6: box is flat along an axis");
+
+        let flattened = source.replace("translate = [1, 0, 0]", "scale = 0");
+        assert_eq!(error(&flattened), "line 6: scale factors must be non-zero");
+        let flattened = source.replace("translate = [1, 0, 0]", "scale = [1, 0, 1]");
+        assert_eq!(error(&flattened), "line 6: scale factors must be non-zero");
+    }
+
+    #[test]
+    fn moving_sphere() {
+        let source = r#"
+[camera]
+shutter = [0, 0.5]
+
+[materials.gray]
+type = "lambertian"
+albedo = [0.5, 0.5, 0.5]
+
+[[objects]]
+type = "sphere"
+center = [0, 0, -1]
+center_end = [2, 0, -1]
+radius = 0.5
+material = "gray"
+"#;
+        let scene = Scene::<f32>::parse(source, Path::new("")).unwrap();
+        let bbox = scene.world[0].bounding_box();
+        assert_eq!(bbox.extent(), Vec3::new(3.0, 1.0, 1.0));
+        let ray = Ray::with_time(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), 1.0);
+        assert!(scene.world.hit(&ray, 0.0..=100.0).is_some());
+
+        for shutter in ["[3, 3]", "[-0.5, 0.5]", "[0.75, 0.25]"] {
+            let source = source.replace("[0, 0.5]", shutter);
+            assert_eq!(
+                error(&source),
+                "line 3: shutter times must satisfy 0 <= open <= close <= 1"
+            );
+        }
+    }
+
+    #[test]
+    fn textured_materials() {
+        let source = r#"
+[materials.floor]
+type = "lambertian"
+albedo = { type = "checker", scale = 1, even = [0, 0, 0], odd = [1, 1, 1] }
+
+[[objects]]
+type = "quad"
+q = [-5, -1, -5]
+u = [10, 0, 0]
+v = [0, 0, 10]
+material = "floor"
+"#;
+        let scene = Scene::<f32>::parse(source, Path::new("")).unwrap();
+        let albedo = |x| {
+            let ray = Ray::new(Vec3::new(x, 0.0, 0.5), Vec3::new(0.0, -1.0, 0.0));
+            let hit = scene.world.hit(&ray, 0.0..=100.0).unwrap();
+            let mut rng = crate::rng::pixel_rng(0, 1, 0, 0);
+            hit.material
+                .scatter(&ray, &hit, &mut rng)
+                .unwrap()
+                .attenuation
+        };
+        assert_ne!(albedo(0.5), albedo(1.5));
+
+        let source = r#"
+[materials.stone]
+type = "lambertian"
+albedo = { type = "noise", pattern = "marble", scale = 4, seed = 2 }
+"#;
+        assert!(Scene::<f32>::parse(source, Path::new("")).is_ok());
+        let source = source.replace("marble", "granite");
+        assert!(error(&source).starts_with("line 2: unknown variant `granite`"));
+
+        let source = r#"
+[materials.earth]
+type = "lambertian"
+albedo = { type = "image", path = "missing.png" }
+"#;
+        assert!(error(source).starts_with("line 2: cannot load 'missing.png'"));
+    }
+}
@@ -1,23 +1,55 @@
+use crate::aabb::Aabb;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
-use crate::vec3::Point3;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::rng::Rng;
+use crate::vec3::{Point3, Vec3};
 use crate::velem::VElem;
-use std::rc::Rc;
+use rand::Rng as _;
+use std::sync::Arc;
 
 pub struct Sphere<T: VElem> {
+    /// center at time 0
     center: Point3<T>,
+    /// distance the center moves from time 0 to time 1, zero for a still sphere
+    motion: Vec3<T>,
     radius: T,
-    material: Rc<dyn Material<T>>,
+    material: Arc<dyn Material<T>>,
 }
 
 impl<T: VElem> Sphere<T> {
     pub fn new(center: Point3<T>, radius: T, material: impl Material<T> + 'static) -> Self {
+        Self::moving(center, center, radius, material)
+    }
+
+    /// sphere moving linearly from `start` at time 0 to `end` at time 1
+    pub fn moving(
+        start: Point3<T>,
+        end: Point3<T>,
+        radius: T,
+        material: impl Material<T> + 'static,
+    ) -> Self {
         Self {
-            center,
+            center: start,
+            motion: end - start,
             radius: T::max(0.0.into(), radius),
-            material: Rc::new(material),
+            material: Arc::new(material),
         }
     }
+
+    /// center at `time`, held at the ends outside of [0, 1] so it stays inside the bounding box
+    fn center(&self, time: T) -> Point3<T> {
+        self.center + self.motion * time.max(T::zero()).min(T::one())
+    }
+
+    /// Maps a point on the unit sphere centered at the origin to (u, v) in [0, 1],
+    /// u going around the Y axis starting from -X and v from the bottom pole (Y = -1) to the top.
+    fn uv(p: Point3<T>) -> (T, T) {
+        let theta = (-p.y()).acos();
+        let phi = T::atan2(-p.z(), p.x()) + T::PI();
+        (phi / T::TAU(), theta / T::PI())
+    }
 }
 
 impl<T: VElem> Hittable<T> for Sphere<T> {
@@ -26,7 +58,8 @@ impl<T: VElem> Hittable<T> for Sphere<T> {
         ray: &crate::ray::Ray<T>,
         ray_t: std::ops::RangeInclusive<T>,
     ) -> Option<HitRecord<T>> {
-        let o_to_c = self.center - ray.origin();
+        let center = self.center(ray.time());
+        let o_to_c = center - ray.origin();
 
         //quaratic equation coefficients
         let a = ray.direction().length_squared();
@@ -57,20 +90,67 @@ impl<T: VElem> Hittable<T> for Sphere<T> {
 
         // Calculate the point of intersection and the normal at the hit point
         let hit_point = ray.at(root);
-        let outward_normal = (hit_point - self.center) / self.radius;
+        let outward_normal = (hit_point - center) / self.radius;
+        let (u, v) = Self::uv(outward_normal);
 
         // Create the hit record and set the face normal
         let mut hit_r = HitRecord {
             t: root,
+            u,
+            v,
             p: hit_point,
             normal: outward_normal,
             front_facing: false,
+            color: None,
             material: self.material.clone(),
         };
         hit_r.set_face_normal(ray, outward_normal);
 
         Some(hit_r)
     }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        // covers the whole sweep from time 0 to 1
+        let rvec = Vec3::from([self.radius; 3]);
+        let start = Aabb::new(self.center - rvec, self.center + rvec);
+        let end = self.center(T::one());
+        start.union(&Aabb::new(end - rvec, end + rvec))
+    }
+
+    /// Uniform over the cone of directions the sphere covers seen from `origin`, or over all
+    /// directions from inside. Moving spheres are sampled where they are at time 0.
+    fn pdf_value(&self, origin: Point3<T>, direction: Vec3<T>) -> T {
+        let ray = Ray::new(origin, direction);
+        if self.hit(&ray, 0.0001.into()..=T::infinity()).is_none() {
+            return T::zero();
+        }
+        let radius_squared = self.radius * self.radius;
+        let distance_squared = (self.center - origin).length_squared();
+        let solid_angle = if distance_squared <= radius_squared {
+            Into::<T>::into(4.0) * T::PI()
+        } else {
+            let cos_theta_max = (T::one() - radius_squared / distance_squared).sqrt();
+            Into::<T>::into(2.0) * T::PI() * (T::one() - cos_theta_max)
+        };
+        T::one() / solid_angle
+    }
+
+    fn random(&self, origin: Point3<T>, rng: &mut Rng) -> Vec3<T> {
+        let towards = self.center - origin;
+        let distance_squared = towards.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return Vec3::random_unit_vec(rng);
+        }
+        // uniform in the cone around the center, z is the cosine to its axis
+        let r1: T = rng.gen_range(0.0f32..1.0).into();
+        let r2: T = rng.gen_range(0.0f32..1.0).into();
+        let cos_theta_max = (T::one() - radius_squared / distance_squared).sqrt();
+        let z = T::one() + r2 * (cos_theta_max - T::one());
+        let phi = T::TAU() * r1;
+        let sin_theta = (T::one() - z * z).sqrt();
+        Onb::new(towards).transform(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
 }
 
 #[cfg(test)]
@@ -79,15 +159,17 @@ mod tests {
     type Ray = crate::ray::Ray<f32>;
     type Sphere = super::Sphere<f32>;
     type Vec3 = crate::vec3::Vec3<f32>;
+    use crate::aabb::Aabb;
     use crate::hittable::Hittable;
     use crate::lambertian::Lambertian;
-    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn test_ray_intersecting_sphere() {
-        let mat = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
         let sphere = Sphere {
             center: Vec3::new(0.0, 0.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
             radius: 1.0,
             material: mat,
         };
@@ -103,9 +185,10 @@ mod tests {
 
     #[test]
     fn test_ray_tangent_to_sphere() {
-        let mat = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
         let sphere = Sphere {
             center: Vec3::new(0.0, 2.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
             radius: 1.0,
             material: mat, /* Material initialization */
         };
@@ -124,9 +207,10 @@ mod tests {
 
     #[test]
     fn test_ray_misses_sphere() {
-        let mat = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
         let sphere = Sphere {
             center: Vec3::new(0.0, 0.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
             radius: 1.0,
             material: mat, /* Material initialization */
         };
@@ -138,9 +222,10 @@ mod tests {
 
     #[test]
     fn test_ray_origin_inside_sphere() {
-        let mat = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
         let sphere = Sphere {
             center: Vec3::new(0.0, 0.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
             radius: 1.0,
             material: mat, /* Material initialization */
         };
@@ -154,11 +239,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bounding_box() {
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let sphere = Sphere {
+            center: Vec3::new(0.0, 1.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
+            radius: 2.0,
+            material: mat,
+        };
+        assert_eq!(
+            sphere.bounding_box(),
+            Aabb::new(Vec3::new(-2.0, -1.0, -7.0), Vec3::new(2.0, 3.0, -3.0))
+        );
+    }
+
+    #[test]
+    fn test_uv() {
+        let uv = |p: Vec3| Sphere::uv(p);
+        assert_eq!(uv(Vec3::new(1.0, 0.0, 0.0)), (0.5, 0.5));
+        assert_eq!(uv(Vec3::new(0.0, 1.0, 0.0)).1, 1.0);
+        assert_eq!(uv(Vec3::new(0.0, -1.0, 0.0)).1, 0.0);
+        assert_eq!(uv(Vec3::new(-1.0, 0.0, 0.0)), (0.0, 0.5));
+        assert_eq!(uv(Vec3::new(0.0, 0.0, 1.0)), (0.25, 0.5));
+        assert_eq!(uv(Vec3::new(0.0, 0.0, -1.0)), (0.75, 0.5));
+    }
+
     #[test]
     fn test_ray_parallel_to_surface() {
-        let mat = Rc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
+        let mat = Arc::new(Lambertian::new(Color::new(0.1, 0.2, 0.5)));
         let sphere = Sphere {
             center: Vec3::new(0.0, 0.0, -5.0),
+            motion: Vec3::new(0.0, 0.0, 0.0),
             radius: 1.0,
             material: mat, /* Material initialization */
         };
@@ -167,4 +279,34 @@ mod tests {
 
         assert!(sphere.hit(&ray, range).is_none());
     }
+
+    #[test]
+    fn test_moving_sphere() {
+        let sphere = Sphere::moving(
+            Vec3::new(0.0, 0.0, -5.0),
+            Vec3::new(2.0, 0.0, -5.0),
+            0.5,
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        );
+        let at = |time| Ray::with_time(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), time);
+        assert!(sphere.hit(&at(0.0), 0.0..=10.0).is_some());
+        // by half time the center is at x = 1 and the ray passes beside it
+        assert!(sphere.hit(&at(0.5), 0.0..=10.0).is_none());
+
+        let ray = Ray::with_time(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), 0.5);
+        let hit = sphere.hit(&ray, 0.0..=10.0).unwrap();
+        assert_eq!(hit.t, 4.5);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        let bbox = sphere.bounding_box();
+        assert_eq!(
+            bbox,
+            Aabb::new(Vec3::new(-0.5, -0.5, -5.5), Vec3::new(2.5, 0.5, -4.5))
+        );
+
+        // the motion stops at the ends instead of leaving the bounding box
+        let ray = Ray::with_time(Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), 3.0);
+        assert_eq!(sphere.hit(&ray, 0.0..=10.0).unwrap().t, 4.5);
+        assert!(sphere.hit(&at(-1.0), 0.0..=10.0).is_some());
+    }
 }
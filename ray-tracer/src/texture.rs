@@ -0,0 +1,274 @@
+use crate::color::Color;
+use crate::image::Image;
+use crate::perlin::Perlin;
+use crate::vec3::Point3;
+use crate::velem::VElem;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Spatially varying color, looked up with the surface coordinates (u, v) and the hit point `p`.
+pub trait Texture<T: VElem>: Send + Sync {
+    fn value(&self, u: T, v: T, p: Point3<T>) -> Color<T>;
+}
+
+/// lets one texture instance be shared between several materials
+impl<T: VElem> Texture<T> for Arc<dyn Texture<T>> {
+    fn value(&self, u: T, v: T, p: Point3<T>) -> Color<T> {
+        self.as_ref().value(u, v, p)
+    }
+}
+
+/// the same color everywhere
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolidColor<T: VElem> {
+    albedo: Color<T>,
+}
+
+impl<T: VElem> SolidColor<T> {
+    pub fn new(albedo: Color<T>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl<T: VElem> Texture<T> for SolidColor<T> {
+    fn value(&self, _u: T, _v: T, _p: Point3<T>) -> Color<T> {
+        self.albedo
+    }
+}
+
+/// Checker pattern of cubes with an edge of `scale` filling space, alternating between two
+/// textures. Being solid, it does not depend on how the surface is parameterized.
+pub struct Checker<T: VElem> {
+    inv_scale: T,
+    even: Arc<dyn Texture<T>>,
+    odd: Arc<dyn Texture<T>>,
+}
+
+impl<T: VElem> Checker<T> {
+    pub fn new(scale: T, even: impl Texture<T> + 'static, odd: impl Texture<T> + 'static) -> Self {
+        Self {
+            inv_scale: T::one() / scale,
+            even: Arc::new(even),
+            odd: Arc::new(odd),
+        }
+    }
+
+    /// checker alternating between two solid colors
+    pub fn colors(scale: T, even: Color<T>, odd: Color<T>) -> Self {
+        Self::new(scale, SolidColor::new(even), SolidColor::new(odd))
+    }
+}
+
+impl<T: VElem> Texture<T> for Checker<T> {
+    fn value(&self, u: T, v: T, p: Point3<T>) -> Color<T> {
+        let cell = |c: T| (self.inv_scale * c).floor().to_i64().unwrap_or(0);
+        if (cell(p.x()) + cell(p.y()) + cell(p.z())) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+/// Image mapped onto the surface coordinates, u runs left to right and v bottom to top.
+/// Lookups outside of [0, 1] are clamped to the border.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTexture<T: VElem> {
+    image: Image<T>,
+}
+
+impl<T: VElem> ImageTexture<T> {
+    pub fn new(image: Image<T>) -> Self {
+        Self { image }
+    }
+
+    /// loads a `.png`, `.ppm`, `.pfm` or `.hdr` image
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
+        Ok(Self::new(Image::open(path)?))
+    }
+}
+
+impl<T: VElem> Texture<T> for ImageTexture<T> {
+    fn value(&self, u: T, v: T, _p: Point3<T>) -> Color<T> {
+        let (width, height) = (self.image.width(), self.image.height());
+        // solid cyan makes a missing image stand out in the render
+        if width == 0 || height == 0 {
+            return Color::new(T::zero(), T::one(), T::one());
+        }
+        let u = u.max(T::zero()).min(T::one());
+        // image rows go from the top down
+        let v = T::one() - v.max(T::zero()).min(T::one());
+        let texel = |t: T, size: u64| {
+            (t * (size as f32).into())
+                .to_u64()
+                .unwrap_or(0)
+                .min(size - 1)
+        };
+        self.image[(texel(u, width), texel(v, height))]
+    }
+}
+
+/// how a [`NoiseTexture`] turns the noise field into a color
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoisePattern {
+    /// plain smooth noise
+    Noise,
+    /// several octaves of noise, looks like clouds or rough stone
+    Turbulence,
+    /// stripes along z distorted by turbulence
+    Marble,
+    /// rings around the y axis distorted by turbulence
+    Wood,
+}
+
+/// Procedural solid texture built on [`Perlin`] noise, scaling `color` by the pattern intensity.
+/// `scale` is the frequency of the pattern, larger values give finer detail.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseTexture<T: VElem> {
+    perlin: Perlin<T>,
+    pattern: NoisePattern,
+    scale: T,
+    color: Color<T>,
+}
+
+impl<T: VElem> NoiseTexture<T> {
+    /// octaves summed up for the turbulence based patterns
+    const DEPTH: usize = 7;
+
+    pub fn new(pattern: NoisePattern, scale: T, color: Color<T>) -> Self {
+        Self::with_seed(pattern, scale, color, 0)
+    }
+
+    /// texture drawing its noise field from `seed`, so that several surfaces don't repeat
+    pub fn with_seed(pattern: NoisePattern, scale: T, color: Color<T>, seed: u64) -> Self {
+        Self {
+            perlin: Perlin::new(seed),
+            pattern,
+            scale,
+            color,
+        }
+    }
+
+    /// pattern intensity at `p` in [0, 1]
+    fn intensity(&self, p: Point3<T>) -> T {
+        let half: T = 0.5.into();
+        let intensity = match self.pattern {
+            NoisePattern::Noise => half * (T::one() + self.perlin.noise(p * self.scale)),
+            NoisePattern::Turbulence => self.perlin.turbulence(p * self.scale, Self::DEPTH),
+            NoisePattern::Marble => {
+                let turbulence = self.perlin.turbulence(p, Self::DEPTH);
+                half * (T::one() + (self.scale * p.z() + Into::<T>::into(10.0) * turbulence).sin())
+            }
+            NoisePattern::Wood => {
+                let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+                let rings = self.scale * radius + self.perlin.turbulence(p, Self::DEPTH);
+                // light wood with darker late wood towards the end of every ring
+                let ring = rings - rings.floor();
+                Into::<T>::into(0.6) + Into::<T>::into(0.4) * (T::one() - ring * ring)
+            }
+        };
+        intensity.max(T::zero()).min(T::one())
+    }
+}
+
+impl<T: VElem> Texture<T> for NoiseTexture<T> {
+    fn value(&self, _u: T, _v: T, p: Point3<T>) -> Color<T> {
+        self.color * self.intensity(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Image = crate::image::Image<f32>;
+    type Point3 = crate::vec3::Point3<f32>;
+    use super::{Checker, ImageTexture, NoisePattern, NoiseTexture, SolidColor, Texture};
+    use std::sync::Arc;
+
+    #[test]
+    fn solid_color() {
+        let t = SolidColor::new(Color::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            t.value(0.5, 0.5, Point3::new(4.0, 5.0, 6.0)),
+            Color::new(0.1, 0.2, 0.3)
+        );
+    }
+
+    #[test]
+    fn checker() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let t = Checker::colors(0.5, black, white);
+        assert_eq!(t.value(0.0, 0.0, Point3::new(0.1, 0.1, 0.1)), black);
+        assert_eq!(t.value(0.0, 0.0, Point3::new(0.6, 0.1, 0.1)), white);
+        assert_eq!(t.value(0.0, 0.0, Point3::new(0.6, 0.6, 0.1)), black);
+        // cells below zero keep alternating
+        assert_eq!(t.value(0.0, 0.0, Point3::new(-0.1, 0.1, 0.1)), white);
+        assert_eq!(t.value(0.0, 0.0, Point3::new(-0.6, 0.1, 0.1)), black);
+
+        // checkers nest
+        let shared: Arc<dyn Texture<f32>> = Arc::new(t);
+        let nested = Checker::new(4.0, shared.clone(), SolidColor::new(white));
+        let p = Point3::new(0.6, 0.1, 0.1);
+        assert_eq!(nested.value(0.0, 0.0, p), shared.value(0.0, 0.0, p));
+        assert_eq!(nested.value(0.0, 0.0, Point3::new(-0.6, 0.1, 0.1)), white);
+    }
+
+    #[test]
+    fn image_texture() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let blue = Color::new(0.0, 0.0, 1.0);
+        // red row on top of a blue one
+        let image = Image::from_pixels(2, 2, vec![red, red, blue, blue]);
+        let t = ImageTexture::new(image);
+        let p = Point3::new(0.0, 0.0, 0.0);
+        assert_eq!(t.value(0.25, 0.9, p), red);
+        assert_eq!(t.value(0.75, 0.1, p), blue);
+        // the borders are clamped
+        assert_eq!(t.value(1.0, 1.0, p), red);
+        assert_eq!(t.value(-3.0, -2.0, p), blue);
+
+        let empty = ImageTexture::new(Image::new(0, 0));
+        assert_eq!(empty.value(0.5, 0.5, p), Color::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn noise_texture() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let points: Vec<_> = (0..200)
+            .map(|i| Point3::new(i as f32 * 0.31, i as f32 * 0.17, i as f32 * -0.23))
+            .collect();
+        for pattern in [
+            NoisePattern::Noise,
+            NoisePattern::Turbulence,
+            NoisePattern::Marble,
+            NoisePattern::Wood,
+        ] {
+            let t = NoiseTexture::new(pattern, 4.0, white);
+            let values: Vec<_> = points.iter().map(|p| t.value(0.0, 0.0, *p).x()).collect();
+            assert!(
+                values.iter().all(|v| (0.0..=1.0).contains(v)),
+                "{:?}",
+                pattern
+            );
+            assert!(
+                values.iter().any(|v| (v - values[0]).abs() > 0.05),
+                "{:?} is flat",
+                pattern
+            );
+            // the same seed gives the same texture
+            let again = NoiseTexture::new(pattern, 4.0, white);
+            assert_eq!(
+                t.value(0.0, 0.0, points[7]),
+                again.value(0.0, 0.0, points[7])
+            );
+        }
+
+        let tinted = NoiseTexture::new(NoisePattern::Marble, 4.0, Color::new(1.0, 0.5, 0.0));
+        let c = tinted.value(0.0, 0.0, points[3]);
+        assert_eq!(c.z(), 0.0);
+        assert!((c.y() * 2.0 - c.x()).abs() < 1e-6);
+    }
+}
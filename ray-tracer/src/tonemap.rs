@@ -0,0 +1,148 @@
+use crate::color::Color;
+use crate::image::Image;
+use crate::velem::VElem;
+use std::str::FromStr;
+
+/// Curve compressing scene radiance into the displayable [0, 1] range
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// no compression, everything above 1.0 is clipped by the encoder
+    Clamp,
+    /// per channel `c / (1 + c)`
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic reference rendering transform
+    Aces,
+}
+
+impl FromStr for Operator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "clamp" | "none" => Ok(Self::Clamp),
+            "reinhard" => Ok(Self::Reinhard),
+            "aces" => Ok(Self::Aces),
+            _ => Err(format!("unknown tone mapping operator '{}'", s)),
+        }
+    }
+}
+
+/// Tone mapping stage sitting between the linear radiance buffer and the low dynamic range
+/// encoders, which apply the sRGB transfer curve to its output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMap<T: VElem> {
+    operator: Operator,
+    exposure: T,
+}
+
+impl<T: VElem> Default for ToneMap<T> {
+    fn default() -> Self {
+        Self::new(Operator::Clamp)
+    }
+}
+
+impl<T: VElem> ToneMap<T> {
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            exposure: T::zero(),
+        }
+    }
+
+    /// exposure adjustment in stops (EV), every stop doubles the radiance
+    pub fn exposure(mut self, exposure: T) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// maps a single linear radiance value to display linear
+    pub fn map(&self, color: Color<T>) -> Color<T> {
+        let exposed = color * Into::<T>::into(2.0).powf(self.exposure);
+        let curve = |c: T| -> T {
+            let c = c.max(T::zero());
+            match self.operator {
+                Operator::Clamp => c.min(T::one()),
+                Operator::Reinhard => c / (T::one() + c),
+                Operator::Aces => {
+                    let a: T = 2.51.into();
+                    let b: T = 0.03.into();
+                    let c2: T = 2.43.into();
+                    let d: T = 0.59.into();
+                    let e: T = 0.14.into();
+                    ((c * (a * c + b)) / (c * (c2 * c + d) + e)).clamp(T::zero(), T::one())
+                }
+            }
+        };
+        Color::new(curve(exposed.x()), curve(exposed.y()), curve(exposed.z()))
+    }
+
+    pub fn apply(&self, image: &Image<T>) -> Image<T> {
+        let mut mapped = image.clone();
+        for pixel in mapped.pixels_mut() {
+            *pixel = self.map(*pixel);
+        }
+        mapped
+    }
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    #[test]
+    fn clamp_test() {
+        let tm = ToneMap::<f32>::default();
+        assert_eq!(
+            tm.map(Color::new(-1.0, 0.5, 4.0)),
+            Color::new(0.0, 0.5, 1.0)
+        );
+    }
+
+    #[test]
+    fn exposure_test() {
+        let tm = ToneMap::<f32>::new(Operator::Clamp).exposure(1.0);
+        assert_eq!(
+            tm.map(Color::new(0.25, 0.5, 0.0)),
+            Color::new(0.5, 1.0, 0.0)
+        );
+        let tm = ToneMap::<f32>::new(Operator::Clamp).exposure(-2.0);
+        assert_eq!(
+            tm.map(Color::new(4.0, 2.0, 1.0)),
+            Color::new(1.0, 0.5, 0.25)
+        );
+    }
+
+    #[test]
+    fn reinhard_test() {
+        let tm = ToneMap::<f32>::new(Operator::Reinhard);
+        assert_eq!(
+            tm.map(Color::new(0.0, 1.0, 3.0)),
+            Color::new(0.0, 0.5, 0.75)
+        );
+    }
+
+    #[test]
+    fn aces_test() {
+        let tm = ToneMap::<f32>::new(Operator::Aces);
+        let c = tm.map(Color::new(0.0, 0.18, 100.0));
+        assert!(c.x() < 0.001);
+        assert!((c.y() - 0.2666).abs() < 0.001, "got {}", c.y());
+        assert_eq!(c.z(), 1.0);
+    }
+
+    #[test]
+    fn apply_test() {
+        let img = Image::from_pixels(1, 1, vec![Color::new(1.0, 1.0, 1.0)]);
+        let mapped = ToneMap::new(Operator::Reinhard).apply(&img);
+        assert_eq!(mapped[(0, 0)], Color::new(0.5, 0.5, 0.5));
+    }
+
+    #[test]
+    fn from_str_test() {
+        assert_eq!("aces".parse(), Ok(Operator::Aces));
+        assert_eq!("Reinhard".parse(), Ok(Operator::Reinhard));
+        assert_eq!("none".parse(), Ok(Operator::Clamp));
+        assert!("filmic".parse::<Operator>().is_err());
+    }
+}
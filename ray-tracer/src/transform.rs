@@ -0,0 +1,217 @@
+use crate::aabb::Aabb;
+use crate::vec3::{Point3, Vec3};
+use crate::velem::VElem;
+
+type Matrix<T> = [[T; 4]; 4];
+
+/// Affine 4x4 transform kept together with its inverse.
+/// Transforms are built from the elementary ones and chained with [`Transform::then`],
+/// so the inverse never has to be computed from a general matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform<T: VElem> {
+    matrix: Matrix<T>,
+    inverse: Matrix<T>,
+}
+
+impl<T: VElem> Default for Transform<T> {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<T: VElem> Transform<T> {
+    pub fn identity() -> Self {
+        Self {
+            matrix: identity(),
+            inverse: identity(),
+        }
+    }
+
+    pub fn translate(offset: Vec3<T>) -> Self {
+        let mut matrix = identity();
+        let mut inverse = identity();
+        for axis in 0..3 {
+            matrix[axis][3] = offset[axis];
+            inverse[axis][3] = -offset[axis];
+        }
+        Self { matrix, inverse }
+    }
+
+    /// scales by a factor per axis, none of them may be zero
+    pub fn scale(factors: Vec3<T>) -> Self {
+        assert!(
+            (0..3).all(|axis| factors[axis] != T::zero()),
+            "zero scale factor"
+        );
+        let mut matrix = identity();
+        let mut inverse = identity();
+        for axis in 0..3 {
+            matrix[axis][axis] = factors[axis];
+            inverse[axis][axis] = T::one() / factors[axis];
+        }
+        Self { matrix, inverse }
+    }
+
+    /// counter-clockwise rotation by `degrees` around `axis`, looking down the axis at the origin
+    pub fn rotate(axis: Vec3<T>, degrees: T) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let k = T::one() - cos;
+        let mut matrix = identity();
+        // Rodrigues' rotation formula
+        matrix[0][0] = cos + a.x() * a.x() * k;
+        matrix[0][1] = a.x() * a.y() * k - a.z() * sin;
+        matrix[0][2] = a.x() * a.z() * k + a.y() * sin;
+        matrix[1][0] = a.y() * a.x() * k + a.z() * sin;
+        matrix[1][1] = cos + a.y() * a.y() * k;
+        matrix[1][2] = a.y() * a.z() * k - a.x() * sin;
+        matrix[2][0] = a.z() * a.x() * k - a.y() * sin;
+        matrix[2][1] = a.z() * a.y() * k + a.x() * sin;
+        matrix[2][2] = cos + a.z() * a.z() * k;
+        // rotations are orthogonal, the inverse is the transpose
+        Self {
+            matrix,
+            inverse: transpose(&matrix),
+        }
+    }
+
+    /// transform applying `self` first and `next` afterwards
+    pub fn then(&self, next: &Self) -> Self {
+        Self {
+            matrix: multiply(&next.matrix, &self.matrix),
+            inverse: multiply(&self.inverse, &next.inverse),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, p: Point3<T>) -> Point3<T> {
+        apply(&self.matrix, p, T::one())
+    }
+
+    /// transforms a direction, which ignores the translation
+    pub fn vector(&self, v: Vec3<T>) -> Vec3<T> {
+        apply(&self.matrix, v, T::zero())
+    }
+
+    /// Transforms a surface normal with the inverse transpose, so that it stays perpendicular to
+    /// the transformed surface under non uniform scaling. The result is not normalized.
+    pub fn normal(&self, n: Vec3<T>) -> Vec3<T> {
+        apply(&transpose(&self.inverse), n, T::zero())
+    }
+
+    /// box enclosing the transformed `bbox`
+    pub fn bounding_box(&self, bbox: &Aabb<T>) -> Aabb<T> {
+        if bbox.is_empty() {
+            return *bbox;
+        }
+        bbox.corners()
+            .into_iter()
+            .map(|corner| self.point(corner))
+            .fold(Aabb::empty(), |acc, p| acc.union(&Aabb::new(p, p)))
+    }
+}
+
+fn identity<T: VElem>() -> Matrix<T> {
+    std::array::from_fn(|row| {
+        std::array::from_fn(|col| if row == col { T::one() } else { T::zero() })
+    })
+}
+
+fn transpose<T: VElem>(m: &Matrix<T>) -> Matrix<T> {
+    std::array::from_fn(|row| std::array::from_fn(|col| m[col][row]))
+}
+
+fn multiply<T: VElem>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T> {
+    std::array::from_fn(|row| {
+        std::array::from_fn(|col| (0..4).fold(T::zero(), |acc, k| acc + a[row][k] * b[k][col]))
+    })
+}
+
+/// `m * (v, w)` dropping the fourth coordinate, which stays `w` for affine transforms
+fn apply<T: VElem>(m: &Matrix<T>, v: Vec3<T>, w: T) -> Vec3<T> {
+    let row = |r: usize| m[r][0] * v.x() + m[r][1] * v.y() + m[r][2] * v.z() + m[r][3] * w;
+    Vec3::new(row(0), row(1), row(2))
+}
+
+#[cfg(test)]
+mod impl_tests {
+
+    use super::*;
+
+    fn close(a: Vec3<f32>, b: Vec3<f32>) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    #[test]
+    fn translate_test() {
+        let t = Transform::translate(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(
+            t.point(Point3::new(1.0, 1.0, 1.0)),
+            Point3::new(2.0, 3.0, 4.0)
+        );
+        // directions are not moved
+        assert_eq!(t.vector(Vec3::new(1.0, 1.0, 1.0)), Vec3::new(1.0, 1.0, 1.0));
+        assert_eq!(
+            t.inverse().point(Point3::new(2.0, 3.0, 4.0)),
+            Point3::new(1.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn rotate_test() {
+        let r = Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 90.0);
+        // counter-clockwise seen from +Y turns -Z into -X
+        assert!(close(
+            r.vector(Vec3::new(0.0, 0.0, -1.0)),
+            Vec3::new(-1.0, 0.0, 0.0)
+        ));
+        let r = Transform::rotate(Vec3::new(0.0, 0.0, 2.0), 90.0);
+        assert!(close(
+            r.vector(Vec3::new(1.0, 0.0, 0.0)),
+            Vec3::new(0.0, 1.0, 0.0)
+        ));
+        assert!(close(
+            r.inverse().vector(Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(1.0, 0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn then_test() {
+        let t = Transform::scale(Vec3::new(2.0, 2.0, 2.0))
+            .then(&Transform::rotate(Vec3::new(0.0, 0.0, 1.0), 90.0))
+            .then(&Transform::translate(Vec3::new(0.0, 0.0, -5.0)));
+        let p = Point3::new(1.0, 0.0, 0.0);
+        let moved = t.point(p);
+        assert!(close(moved, Point3::new(0.0, 2.0, -5.0)));
+        assert!(close(t.inverse().point(moved), p));
+    }
+
+    #[test]
+    fn normal_test() {
+        // squash a 45 degree slope along x, the normal has to tilt towards x
+        let t = Transform::<f32>::scale(Vec3::new(0.5, 1.0, 1.0));
+        let surface = t.vector(Vec3::new(1.0, 1.0, 0.0));
+        let normal = t.normal(Vec3::new(1.0, -1.0, 0.0));
+        assert!(surface.dot(&normal).abs() < 1e-6);
+        assert!(close(
+            normal.unit_vector(),
+            Vec3::new(2.0, -1.0, 0.0).unit_vector()
+        ));
+    }
+
+    #[test]
+    fn bounding_box_test() {
+        let bbox = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let t = Transform::rotate(Vec3::new(0.0, 1.0, 0.0), 45.0);
+        let e = t.bounding_box(&bbox).extent();
+        assert!((e.x() - 8.0f32.sqrt()).abs() < 1e-5 && (e.y() - 2.0).abs() < 1e-5);
+        assert!(t.bounding_box(&Aabb::empty()).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Point3;
+use crate::velem::VElem;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+/// Möller–Trumbore ray / triangle intersection,
+/// returns the ray parameter t and the barycentric coordinates (b1, b2) of `p1` and `p2`.
+pub(crate) fn intersect<T: VElem>(
+    [p0, p1, p2]: [Point3<T>; 3],
+    ray: &Ray<T>,
+    ray_t: RangeInclusive<T>,
+) -> Option<(T, T, T)> {
+    let edge1 = p1 - p0;
+    let edge2 = p2 - p0;
+    let pvec = ray.direction().cross(&edge2);
+    let det = edge1.dot(&pvec);
+
+    // No hit if the ray is parallel to the triangle plane (or the triangle is degenerate).
+    if det.abs() < 1e-12.into() {
+        return None;
+    }
+    let inv_det = T::one() / det;
+
+    let tvec = ray.origin() - p0;
+    let b1 = tvec.dot(&pvec) * inv_det;
+    if b1 < T::zero() || b1 > T::one() {
+        return None;
+    }
+
+    let qvec = tvec.cross(&edge1);
+    let b2 = ray.direction().dot(&qvec) * inv_det;
+    if b2 < T::zero() || b1 + b2 > T::one() {
+        return None;
+    }
+
+    let t = edge2.dot(&qvec) * inv_det;
+    if !ray_t.contains(&t) {
+        return None;
+    }
+    Some((t, b1, b2))
+}
+
+/// box around the three corners, padded so that axis aligned triangles keep some volume
+pub(crate) fn bounding_box<T: VElem>([p0, p1, p2]: [Point3<T>; 3]) -> Aabb<T> {
+    Aabb::new(p0, p1).union(&Aabb::new(p2, p2)).pad(1e-4.into())
+}
+
+/// Single flat shaded triangle, see [`crate::mesh::TriangleMesh`] for many triangles
+/// sharing their vertices.
+/// The front face is the one the corners `a`, `b`, `c` wind counter-clockwise around.
+pub struct Triangle<T: VElem> {
+    vertices: [Point3<T>; 3],
+    material: Arc<dyn Material<T>>,
+}
+
+impl<T: VElem> Triangle<T> {
+    pub fn new(
+        a: Point3<T>,
+        b: Point3<T>,
+        c: Point3<T>,
+        material: impl Material<T> + 'static,
+    ) -> Self {
+        Self {
+            vertices: [a, b, c],
+            material: Arc::new(material),
+        }
+    }
+}
+
+impl<T: VElem> Hittable<T> for Triangle<T> {
+    fn hit(&self, ray: &Ray<T>, ray_t: RangeInclusive<T>) -> Option<HitRecord<T>> {
+        let (t, b1, b2) = intersect(self.vertices, ray, ray_t)?;
+        let [p0, p1, p2] = self.vertices;
+        let outward_normal = (p1 - p0).cross(&(p2 - p0)).unit_vector();
+        let mut hit_r = HitRecord {
+            t,
+            u: b1,
+            v: b2,
+            p: ray.at(t),
+            normal: outward_normal,
+            front_facing: false,
+            color: None,
+            material: self.material.clone(),
+        };
+        hit_r.set_face_normal(ray, outward_normal);
+        Some(hit_r)
+    }
+
+    fn bounding_box(&self) -> Aabb<T> {
+        bounding_box(self.vertices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    type Color = crate::color::Color<f32>;
+    type Ray = crate::ray::Ray<f32>;
+    type Triangle = super::Triangle<f32>;
+    type Vec3 = crate::vec3::Vec3<f32>;
+    use crate::hittable::Hittable;
+    use crate::lambertian::Lambertian;
+
+    fn unit_triangle() -> Triangle {
+        // right triangle on the z = -2 plane facing +z
+        Triangle::new(
+            Vec3::new(0.0, 0.0, -2.0),
+            Vec3::new(1.0, 0.0, -2.0),
+            Vec3::new(0.0, 1.0, -2.0),
+            Lambertian::new(Color::new(0.5, 0.5, 0.5)),
+        )
+    }
+
+    #[test]
+    fn test_ray_hits_triangle() {
+        let triangle = unit_triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = triangle
+            .hit(&ray, 0.0..=10.0)
+            .expect("Ray should hit the triangle");
+        assert_eq!(hit.t, 2.0);
+        assert_eq!(hit.p, Vec3::new(0.25, 0.5, -2.0));
+        assert_eq!((hit.u, hit.v), (0.25, 0.5));
+        assert!(hit.front_facing);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_ray_hits_back_face() {
+        let triangle = unit_triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -4.0), Vec3::new(0.0, 0.0, 1.0));
+        let hit = triangle
+            .hit(&ray, 0.0..=10.0)
+            .expect("Ray should hit the triangle");
+        assert!(!hit.front_facing);
+        assert_eq!(hit.normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn test_ray_misses_triangle() {
+        let triangle = unit_triangle();
+        // inside the bounding square but past the hypotenuse
+        let ray = Ray::new(Vec3::new(0.75, 0.75, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(triangle.hit(&ray, 0.0..=10.0).is_none());
+        // parallel to the plane
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(triangle.hit(&ray, 0.0..=10.0).is_none());
+        // out of range
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(triangle.hit(&ray, 0.0..=1.0).is_none());
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let e = unit_triangle().bounding_box().extent();
+        assert_eq!((e.x(), e.y()), (1.0, 1.0));
+        assert!(e.z() > 0.0 && e.z() < 1e-3);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::velem::VElem;
 use num_traits::Zero;
 use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
 use std::ops;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -62,37 +63,43 @@ where
     /// generates a fully random number
     /// **IMPORTANT** the rnage here is -1e10 to 1e10 due to rand crate limitations on using MAX
     /// consts
-    pub fn random() -> Self {
-        Self::random_range(Into::<T>::into(-1e10)..=1e10.into())
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::random_range(Into::<T>::into(-1e10)..=1e10.into(), rng)
     }
 
-    pub fn random_range(range: ops::RangeInclusive<T>) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn random_range<R: Rng + ?Sized>(range: ops::RangeInclusive<T>, rng: &mut R) -> Self {
         let dist = Uniform::<T>::from(range);
         Self {
-            xyz: [
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-                dist.sample(&mut rng),
-            ],
+            xyz: [dist.sample(rng), dist.sample(rng), dist.sample(rng)],
+        }
+    }
+
+    pub fn random_unit_sphere<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        loop {
+            let v = Self::random_range(-T::one()..=T::one(), rng);
+            if v.length_squared() < T::one() {
+                return v;
+            }
         }
     }
 
-    pub fn random_unit_sphere() -> Self {
+    /// random point inside the unit disk on the xy plane, used for sampling the camera lens
+    pub fn random_in_unit_disk<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let dist = Uniform::<T>::from(-T::one()..=T::one());
         loop {
-            let v = Self::random_range(-T::one()..=T::one());
+            let v = Self::new(dist.sample(rng), dist.sample(rng), T::zero());
             if v.length_squared() < T::one() {
                 return v;
             }
         }
     }
 
-    pub fn random_unit_vec() -> Self {
-        Self::random_unit_sphere().unit_vector()
+    pub fn random_unit_vec<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self::random_unit_sphere(rng).unit_vector()
     }
 
-    pub fn random_on_hemisphere(normal: Self) -> Self {
-        let v_sp = Self::random_unit_sphere();
+    pub fn random_on_hemisphere<R: Rng + ?Sized>(normal: Self, rng: &mut R) -> Self {
+        let v_sp = Self::random_unit_sphere(rng);
         if v_sp.dot(&normal) >= T::zero() {
             v_sp
         } else {
@@ -100,6 +107,16 @@ where
         }
     }
 
+    /// Random direction on the hemisphere around +Z with a density proportional to the cosine
+    /// to the Z axis, which is what an ideal diffuse surface scatters
+    pub fn random_cosine_direction<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let r1: T = rng.gen_range(0.0f32..1.0).into();
+        let r2: T = rng.gen_range(0.0f32..1.0).into();
+        let phi = T::TAU() * r1;
+        let r = r2.sqrt();
+        Self::new(phi.cos() * r, phi.sin() * r, (T::one() - r2).sqrt())
+    }
+
     pub fn reflect(&self, normal: Self) -> Self {
         *self - normal * Into::<T>::into(2.0) * self.dot(&normal)
     }
@@ -116,6 +133,11 @@ where
 mod impl_tests {
 
     use super::*;
+    use rand::SeedableRng;
+
+    fn rng() -> crate::rng::Rng {
+        crate::rng::Rng::seed_from_u64(0)
+    }
 
     #[test]
     fn new_xyz_tests() {
@@ -157,7 +179,7 @@ mod impl_tests {
 
     #[test]
     fn random_vec() {
-        let rv = Vec3::random();
+        let rv = Vec3::random(&mut rng());
         assert!(-1e10 <= rv.x());
         assert!(rv.x() <= 1e10);
         assert!(-1e10 <= rv.y());
@@ -168,7 +190,7 @@ mod impl_tests {
 
     #[test]
     fn random_range() {
-        let rv = Vec3::random_range(0.0..=1e20);
+        let rv = Vec3::random_range(0.0..=1e20, &mut rng());
         assert!(0.0 <= rv.x());
         assert!(rv.x() <= 1e20);
         assert!(0.0 <= rv.y());
@@ -179,7 +201,7 @@ mod impl_tests {
 
     #[test]
     fn random_unit_sphere() {
-        let rs = Vec3::random_unit_sphere();
+        let rs = Vec3::random_unit_sphere(&mut rng());
         assert!(-1.0 <= rs.x());
         assert!(rs.x() <= 1.0);
         assert!(-1.0 <= rs.y());
@@ -188,13 +210,27 @@ mod impl_tests {
         assert!(rs.z() <= 1.0);
     }
 
+    #[test]
+    fn random_in_unit_disk() {
+        let rd = Vec3::<f32>::random_in_unit_disk(&mut rng());
+        assert!(rd.length_squared() < 1.0);
+        assert_eq!(rd.z(), 0.0);
+    }
+
     #[test]
     fn random_unit_vec() {
-        let ru = Vec3::<f32>::random_unit_vec();
+        let ru = Vec3::<f32>::random_unit_vec(&mut rng());
         // forgive mistakes up to 0.000001
         assert!(ru.length() + 0.000001 >= 1.0);
     }
 
+    #[test]
+    fn random_cosine_direction() {
+        let d = Vec3::<f32>::random_cosine_direction(&mut rng());
+        assert!((d.length() - 1.0).abs() < 1e-6);
+        assert!(d.z() >= 0.0);
+    }
+
     #[test]
     fn reflect() {
         let v = Vec3::new(3.0, 4.0, 0.0);
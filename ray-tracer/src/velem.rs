@@ -1,13 +1,17 @@
-use num_traits::Float;
+use num_traits::{Float, FloatConst};
 
 pub trait VElem:
     Float
+    + FloatConst
     + From<f32>
     + Default
     + std::fmt::Display
     + std::fmt::Debug
     + rand::distributions::uniform::SampleBorrow<Self>
-    + rand::distributions::uniform::SampleUniform
+    + rand::distributions::uniform::SampleUniform<Sampler: Send + Sync>
+    + Send
+    + Sync
+    + 'static
 {
 }
 